@@ -1,7 +1,14 @@
 extern crate clap;
 
+use std::{path::PathBuf, time::SystemTime};
+
 use clap::{ArgGroup, Args, Parser, Subcommand};
 
+use crate::{
+    commands::{blame::parse_line_range, log::parse_date},
+    hash::HashAlgorithm,
+};
+
 #[derive(Debug, Parser)]
 #[command(about, long_about = None)]
 pub struct Arguments {
@@ -24,26 +31,80 @@ pub enum Commands {
     Jump(JumpCommand),
 
     #[clap(name = "new_branch")]
-    #[clap(about = "Create a new branch from the current commit")]
+    #[clap(about = "Create a new branch from the current commit or restore a deleted one")]
     NewBranch(NewBranchCommand),
 
     #[clap(about = "Merge the given branch to master")]
     Merge(MergeCommand),
 
     #[clap(about = "Log commits history until the current one")]
-    Log,
+    Log(LogCommand),
+
+    #[clap(about = "Verify the integrity of the repository")]
+    Fsck(FsckCommand),
+
+    #[clap(about = "Upgrade the repository to the current format version")]
+    Upgrade,
+
+    #[clap(about = "Show movements of HEAD or of the given branch")]
+    Reflog(ReflogCommand),
+
+    #[clap(about = "Show a commit with its changes or a file at <rev>:<path>")]
+    Show(ShowCommand),
+
+    #[clap(about = "Restore files and folders from a commit, discarding local changes")]
+    Restore(RestoreCommand),
+
+    #[clap(about = "Move the current branch and HEAD to the given commit")]
+    Reset(ResetCommand),
+
+    #[clap(about = "Create a commit undoing the changes of the given commit")]
+    Revert(RevertCommand),
+
+    #[clap(name = "cherry-pick")]
+    #[clap(about = "Replay the changes of the given commits on the current branch")]
+    CherryPick(CherryPickCommand),
+
+    #[clap(about = "Replay the commits of the current branch on top of another commit")]
+    Rebase(RebaseCommand),
+
+    #[clap(about = "Set local changes aside and bring them back later")]
+    Stash(StashCommand),
+
+    #[clap(about = "Show the commit which has last changed every line of the file")]
+    Blame(BlameCommand),
+
+    #[clap(about = "Find the commit which has introduced a bug using binary search")]
+    Bisect(BisectCommand),
+
+    #[clap(about = "Search a regular expression in the working tree or in the given commits")]
+    Grep(GrepCommand),
+
+    #[clap(about = "Store large files outside of snapshots and prune them")]
+    Lfs(LfsCommand),
 }
 
 #[derive(Debug, Args)]
 pub struct InitCommand {
     #[clap(long, short)]
-    pub path: String,
+    pub path: PathBuf,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value = "sha1",
+        help = "Hash algorithm of commits and large objects"
+    )]
+    pub hash: HashAlgorithm,
 }
 
 #[derive(Debug, Args)]
 pub struct CommitCommand {
-    #[clap(long, short)]
-    pub message: String,
+    #[clap(long, short, required_unless_present = "amend")]
+    pub message: Option<String>,
+
+    #[clap(long, help = "Replace the last commit of the branch")]
+    pub amend: bool,
 }
 
 #[derive(Debug, Args)]
@@ -58,12 +119,21 @@ pub struct JumpCommand {
 
     #[clap(long, short)]
     pub branch: Option<String>,
+
+    #[clap(long, conflicts_with = "merge", help = "Discard local changes")]
+    pub force: bool,
+
+    #[clap(long, help = "Carry local changes over to the new commit")]
+    pub merge: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct NewBranchCommand {
     #[clap(long)]
     pub name: String,
+
+    #[clap(long, help = "Restore a deleted branch from its reflog")]
+    pub restore: bool,
 }
 
 #[derive(Debug, Args)]
@@ -71,3 +141,272 @@ pub struct MergeCommand {
     #[clap(long, short)]
     pub branch: String,
 }
+
+#[derive(Debug, Args)]
+pub struct LogCommand {
+    #[clap(long, help = "Print every commit on a single line")]
+    pub oneline: bool,
+
+    #[clap(
+        short = 'n',
+        long = "max-count",
+        help = "Log at most this many commits"
+    )]
+    pub max_count: Option<usize>,
+
+    #[clap(long, value_parser = parse_date, help = "Log commits made after the date")]
+    pub since: Option<SystemTime>,
+
+    #[clap(long, value_parser = parse_date, help = "Log commits made before the date")]
+    pub until: Option<SystemTime>,
+
+    #[clap(long, help = "Log commits whose author contains the text")]
+    pub author: Option<String>,
+
+    #[clap(long, help = "Log commits whose message contains the text")]
+    pub grep: Option<String>,
+
+    #[clap(long, help = "Log commits of all branches")]
+    pub all: bool,
+
+    #[clap(long, help = "Draw the commit graph with branch decorations")]
+    pub graph: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "oneline",
+        help = "Print commits using a template with %H, %h, %P, %an, %ad, %s, %B and %n"
+    )]
+    pub format: Option<String>,
+
+    #[clap(last = true, help = "Log only commits changing these paths")]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct FsckCommand {
+    #[clap(long)]
+    pub repair: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ReflogCommand {
+    #[clap(long, short)]
+    pub branch: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ShowCommand {
+    pub revision: String,
+}
+
+#[derive(Debug, Args)]
+pub struct RestoreCommand {
+    #[clap(long, short, default_value = "HEAD")]
+    pub source: String,
+
+    #[clap(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+#[clap(group(
+    ArgGroup::new("mode")
+    .args(&["soft", "mixed", "hard"])
+))]
+pub struct ResetCommand {
+    #[clap(long, help = "Keep the working tree")]
+    pub soft: bool,
+
+    #[clap(long, help = "Keep the working tree, this is the default")]
+    pub mixed: bool,
+
+    #[clap(long, help = "Replace the working tree with the commit")]
+    pub hard: bool,
+
+    pub revision: String,
+}
+
+#[derive(Debug, Args)]
+pub struct RevertCommand {
+    pub revision: String,
+}
+
+#[derive(Debug, Args)]
+#[clap(group(
+    ArgGroup::new("action")
+    .required(true)
+    .args(&["revisions", "continue_", "abort"])
+))]
+pub struct CherryPickCommand {
+    pub revisions: Vec<String>,
+
+    #[clap(long = "continue", help = "Commit the resolved conflict and continue")]
+    pub continue_: bool,
+
+    #[clap(long, help = "Return to the commit the cherry-pick has started from")]
+    pub abort: bool,
+}
+
+#[derive(Debug, Args)]
+#[clap(group(
+    ArgGroup::new("action")
+    .required(true)
+    .args(&["upstream", "continue_", "abort"])
+))]
+pub struct RebaseCommand {
+    pub upstream: Option<String>,
+
+    #[clap(
+        long,
+        requires = "upstream",
+        help = "Replay the commits on top of this commit instead of upstream"
+    )]
+    pub onto: Option<String>,
+
+    #[clap(long = "continue", help = "Commit the resolved conflict and continue")]
+    pub continue_: bool,
+
+    #[clap(long, help = "Return to the commit the rebase has started from")]
+    pub abort: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StashCommand {
+    #[clap(subcommand)]
+    pub action: StashAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StashAction {
+    #[clap(about = "Save local changes and reset the working tree")]
+    Push {
+        #[clap(long, short)]
+        message: Option<String>,
+    },
+
+    #[clap(about = "List stashes, the newest one first")]
+    List,
+
+    #[clap(about = "Show changes saved in the stash")]
+    Show {
+        #[clap(default_value_t = 0)]
+        index: usize,
+    },
+
+    #[clap(about = "Apply the stash and remove it from the list")]
+    Pop {
+        #[clap(default_value_t = 0)]
+        index: usize,
+    },
+
+    #[clap(about = "Apply the stash keeping it in the list")]
+    Apply {
+        #[clap(default_value_t = 0)]
+        index: usize,
+    },
+
+    #[clap(about = "Remove the stash from the list")]
+    Drop {
+        #[clap(default_value_t = 0)]
+        index: usize,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct BlameCommand {
+    pub path: PathBuf,
+
+    #[clap(default_value = "HEAD")]
+    pub revision: String,
+
+    #[clap(
+        short = 'L',
+        value_parser = parse_line_range,
+        help = "Blame only the lines <start>,<end> or <start>, counting from 1"
+    )]
+    pub lines: Option<(usize, Option<usize>)>,
+}
+
+#[derive(Debug, Args)]
+pub struct BisectCommand {
+    #[clap(subcommand)]
+    pub action: BisectAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BisectAction {
+    #[clap(about = "Start a bisect, optionally with the bad commit and good commits")]
+    Start {
+        bad: Option<String>,
+        good: Vec<String>,
+    },
+
+    #[clap(about = "Mark the commit, the current one by default, as good")]
+    Good { revision: Option<String> },
+
+    #[clap(about = "Mark the commit, the current one by default, as bad")]
+    Bad { revision: Option<String> },
+
+    #[clap(about = "Mark the commit, the current one by default, as impossible to test")]
+    Skip { revision: Option<String> },
+
+    #[clap(about = "Finish the bisect and return to the commit it has started from")]
+    Reset,
+
+    #[clap(about = "Test commits with the command: 0 is good, 125 is skip, up to 127 is bad")]
+    Run {
+        #[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct GrepCommand {
+    pub pattern: String,
+
+    #[clap(help = "Search these commits instead of the working tree")]
+    pub revisions: Vec<String>,
+
+    #[clap(short, long = "ignore-case", help = "Ignore the case of letters")]
+    pub ignore_case: bool,
+
+    #[clap(short = 'n', long = "line-number", help = "Print line numbers")]
+    pub line_number: bool,
+
+    #[clap(last = true, help = "Search only files inside these paths")]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct LfsCommand {
+    #[clap(subcommand)]
+    pub action: LfsAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LfsAction {
+    #[clap(about = "Store files matching the glob pattern as large objects")]
+    Track { pattern: String },
+
+    #[clap(about = "Stop storing files matching the glob pattern as large objects")]
+    Untrack { pattern: String },
+
+    #[clap(about = "Store binary files larger than the size in bytes as large objects")]
+    Threshold {
+        #[clap(help = "Size in bytes, the threshold is disabled if it is not given")]
+        size: Option<u64>,
+    },
+
+    #[clap(name = "ls")]
+    #[clap(about = "List files of the commit which are stored as large objects")]
+    List {
+        #[clap(default_value = "HEAD")]
+        revision: String,
+    },
+
+    #[clap(
+        about = "Remove large objects unused by HEAD, the last commits of branches and stashes"
+    )]
+    Prune,
+}
@@ -0,0 +1,350 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    process::Command,
+};
+
+use crate::{
+    error::VcsError,
+    json_files::{BisectData, CommitData},
+    repo_file_manager::{get_repo_dir, FileChange},
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum BisectResult {
+    UncommitedChanges {
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    /// Another bisect has to be reset first
+    InProgress,
+    /// There is no bisect to mark commits in or reset
+    NotInProgress,
+    /// A bad commit and at least one good commit are needed to start jumping
+    Waiting {
+        has_bad: bool,
+        has_good: bool,
+    },
+    /// The bad commit is an ancestor of a good commit
+    GoodAfterBad,
+    /// The working tree contains the commit to test
+    Jumped {
+        commit: String,
+        /// Commits which may be the first bad one except for the tested one and the bad one
+        remaining: usize,
+    },
+    Found {
+        commit_data: CommitData,
+    },
+    /// Only skipped commits are left, the first bad commit is one of these
+    OnlySkipped {
+        commits: Vec<String>,
+    },
+    /// The command of `bisect run` has exited with a code which is neither good, bad nor skip
+    RunFailed {
+        commit: String,
+        code: Option<i32>,
+    },
+    Reset {
+        commit: String,
+        branch: String,
+    },
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum BisectMark {
+    Good,
+    Bad,
+    Skip,
+}
+
+/// Get the changes of the working tree, which would be lost by jumping
+fn get_local_changes(
+    vcs_state_manager: &mut VcsStateManager,
+) -> Result<Vec<(FileChange, PathBuf)>, VcsError> {
+    let cur_commit = vcs_state_manager.get_commit()?;
+    vcs_state_manager.get_file_changes_commit(&cur_commit)
+}
+
+/// Get the commit and all commits it descends from using the parents of all commits
+fn get_ancestors(
+    parents: &HashMap<String, Vec<String>>,
+    commit: &str,
+) -> Result<HashSet<String>, VcsError> {
+    let mut ancestors: HashSet<String> = HashSet::new();
+    let mut stack = vec![commit.to_string()];
+    while let Some(commit) = stack.pop() {
+        if ancestors.contains(&commit) {
+            continue;
+        }
+        let commit_parents = parents
+            .get(&commit)
+            .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+        stack.extend(commit_parents.iter().cloned());
+        ancestors.insert(commit);
+    }
+    Ok(ancestors)
+}
+
+/// Count the ancestors of every candidate which are candidates themselves
+///
+/// Ancestors of a candidate which are candidates can only be reached through
+/// other candidates, so the sets are collected in one pass with parents first.
+fn count_candidate_ancestors(
+    parents: &HashMap<String, Vec<String>>,
+    candidates: &HashSet<String>,
+) -> HashMap<String, usize> {
+    // Depth first search, a commit is added to the order after its parents
+    let mut order: Vec<&String> = Vec::new();
+    let mut visited: HashSet<&String> = HashSet::new();
+    for candidate in candidates.iter() {
+        let mut stack = vec![(candidate, false)];
+        while let Some((commit, parents_done)) = stack.pop() {
+            if parents_done {
+                order.push(commit);
+                continue;
+            }
+            if !visited.insert(commit) {
+                continue;
+            }
+            stack.push((commit, true));
+            for parent in parents[commit].iter() {
+                if candidates.contains(parent) && !visited.contains(parent) {
+                    stack.push((parent, false));
+                }
+            }
+        }
+    }
+
+    let indices: HashMap<&String, usize> = order.iter().enumerate().map(|(i, x)| (*x, i)).collect();
+    let words = order.len().div_ceil(64);
+    let mut ancestors: Vec<Vec<u64>> = Vec::with_capacity(order.len());
+    for (i, commit) in order.iter().enumerate() {
+        let mut bits = vec![0u64; words];
+        bits[i / 64] |= 1 << (i % 64);
+        for parent in parents[*commit].iter() {
+            if let Some(&parent) = indices.get(parent) {
+                for (word, parent_word) in bits.iter_mut().zip(ancestors[parent].iter()) {
+                    *word |= parent_word;
+                }
+            }
+        }
+        ancestors.push(bits);
+    }
+
+    order
+        .into_iter()
+        .zip(ancestors)
+        .map(|(commit, bits)| {
+            let count = bits.iter().map(|x| x.count_ones() as usize).sum();
+            (commit.clone(), count)
+        })
+        .collect()
+}
+
+/// Jump to the commit splitting the remaining commits in half or report the first bad commit
+///
+/// The remaining commits are ancestors of the bad commit which are not ancestors of any
+/// good commit. The chosen commit has the number of remaining ancestors closest to a half.
+fn next_step(
+    vcs_state_manager: &mut VcsStateManager,
+    bisect: &BisectData,
+) -> Result<BisectResult, VcsError> {
+    let bad = match &bisect.bad {
+        Some(bad) if !bisect.good.is_empty() => bad,
+        _ => {
+            return Ok(BisectResult::Waiting {
+                has_bad: bisect.bad.is_some(),
+                has_good: !bisect.good.is_empty(),
+            })
+        }
+    };
+
+    // The commit list is read once, every lookup of commit data reads all of it
+    let commit_list = vcs_state_manager.get_commit_list()?;
+    let parents: HashMap<String, Vec<String>> = commit_list
+        .commits
+        .iter()
+        .map(|x| (x.hash.clone(), x.parents.clone()))
+        .collect();
+
+    let mut excluded: HashSet<String> = HashSet::new();
+    for good in bisect.good.iter() {
+        excluded.extend(get_ancestors(&parents, good)?);
+    }
+    if excluded.contains(bad) {
+        return Ok(BisectResult::GoodAfterBad);
+    }
+    let bad_ancestors = get_ancestors(&parents, bad)?;
+    let candidates: HashSet<String> = bad_ancestors.difference(&excluded).cloned().collect();
+    // Commit list order makes the choice between equally good commits stable
+    let testable: Vec<String> = commit_list
+        .commits
+        .into_iter()
+        .map(|x| x.hash)
+        .filter(|x| candidates.contains(x) && x != bad && !bisect.skipped.contains(x))
+        .collect();
+
+    if testable.is_empty() {
+        if candidates.len() == 1 {
+            let commit_data = vcs_state_manager
+                .get_commit_data(bad)?
+                .ok_or_else(|| VcsError::UnknownRevision(bad.clone()))?;
+            return Ok(BisectResult::Found { commit_data });
+        }
+        let mut commits: Vec<String> = candidates.into_iter().collect();
+        commits.sort();
+        return Ok(BisectResult::OnlySkipped { commits });
+    }
+
+    let reached = count_candidate_ancestors(&parents, &candidates);
+    let mut best: (usize, &String) = (0, &testable[0]);
+    for commit in testable.iter() {
+        let reached = reached[commit];
+        let score = reached.min(candidates.len() - reached);
+        if score > best.0 {
+            best = (score, commit);
+        }
+    }
+    let commit = best.1.clone();
+
+    let file_changes = get_local_changes(vcs_state_manager)?;
+    if !file_changes.is_empty() {
+        return Ok(BisectResult::UncommitedChanges { file_changes });
+    }
+    vcs_state_manager.jump_to_commit(&commit, None)?;
+    Ok(BisectResult::Jumped {
+        commit,
+        remaining: testable.len() - 1,
+    })
+}
+
+/// Start a bisect, optionally marking the bad commit and good commits right away
+pub fn bisect_start_in_repo(
+    repo_dir: PathBuf,
+    bad: Option<&str>,
+    good: &[String],
+) -> Result<BisectResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    if vcs_state_manager.get_bisect()?.is_some() {
+        return Ok(BisectResult::InProgress);
+    }
+    let file_changes = get_local_changes(&mut vcs_state_manager)?;
+    if !file_changes.is_empty() {
+        return Ok(BisectResult::UncommitedChanges { file_changes });
+    }
+
+    let mut bisect = BisectData {
+        orig_commit: vcs_state_manager.get_commit()?,
+        orig_branch: vcs_state_manager.get_branch()?,
+        bad: None,
+        good: Vec::new(),
+        skipped: Vec::new(),
+    };
+    if let Some(bad) = bad {
+        bisect.bad = Some(vcs_state_manager.resolve_revision(bad)?);
+    }
+    for revision in good {
+        bisect
+            .good
+            .push(vcs_state_manager.resolve_revision(revision)?);
+    }
+    vcs_state_manager.set_bisect(Some(&bisect))?;
+    next_step(&mut vcs_state_manager, &bisect)
+}
+
+/// Mark the commit, HEAD if no revision is given, and jump to the next commit to test
+pub fn bisect_mark_in_repo(
+    repo_dir: PathBuf,
+    mark: BisectMark,
+    revision: Option<&str>,
+) -> Result<BisectResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let mut bisect = match vcs_state_manager.get_bisect()? {
+        Some(bisect) => bisect,
+        None => return Ok(BisectResult::NotInProgress),
+    };
+    let commit = vcs_state_manager.resolve_revision(revision.unwrap_or("HEAD"))?;
+    match mark {
+        BisectMark::Good => bisect.good.push(commit),
+        BisectMark::Bad => bisect.bad = Some(commit),
+        BisectMark::Skip => bisect.skipped.push(commit),
+    }
+    vcs_state_manager.set_bisect(Some(&bisect))?;
+    next_step(&mut vcs_state_manager, &bisect)
+}
+
+/// Finish the bisect and return to the commit it has started from
+pub fn bisect_reset_in_repo(repo_dir: PathBuf) -> Result<BisectResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let bisect = match vcs_state_manager.get_bisect()? {
+        Some(bisect) => bisect,
+        None => return Ok(BisectResult::NotInProgress),
+    };
+    let file_changes = get_local_changes(&mut vcs_state_manager)?;
+    if !file_changes.is_empty() {
+        return Ok(BisectResult::UncommitedChanges { file_changes });
+    }
+
+    vcs_state_manager.jump_to_commit(&bisect.orig_commit, Some(&bisect.orig_branch))?;
+    vcs_state_manager.set_bisect(None)?;
+    Ok(BisectResult::Reset {
+        commit: vcs_state_manager.get_commit()?,
+        branch: vcs_state_manager.get_branch()?,
+    })
+}
+
+/// Test commits with the command until the first bad commit is found
+///
+/// The command is run in the repository folder. Exit code 0 marks the commit
+/// as good, 125 skips it, any other code below 128 marks it as bad.
+pub fn bisect_run_in_repo(repo_dir: PathBuf, command: &[String]) -> Result<BisectResult, VcsError> {
+    let (program, args) = command.split_first().ok_or(VcsError::EmptyCommand)?;
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let bisect = match vcs_state_manager.get_bisect()? {
+        Some(bisect) => bisect,
+        None => return Ok(BisectResult::NotInProgress),
+    };
+    let mut result = next_step(&mut vcs_state_manager, &bisect)?;
+    loop {
+        let commit = match result {
+            BisectResult::Jumped { commit, .. } => commit,
+            result => return Ok(result),
+        };
+        let status = Command::new(program)
+            .args(args)
+            .current_dir(&repo_dir)
+            .status()?;
+        let mark = match status.code() {
+            Some(0) => BisectMark::Good,
+            Some(125) => BisectMark::Skip,
+            Some(code) if code < 128 => BisectMark::Bad,
+            code => return Ok(BisectResult::RunFailed { commit, code }),
+        };
+        result = bisect_mark_in_repo(repo_dir.clone(), mark, Some(&commit))?;
+    }
+}
+
+pub fn bisect_start(bad: Option<&str>, good: &[String]) -> Result<BisectResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    bisect_start_in_repo(repo_dir, bad, good)
+}
+
+pub fn bisect_mark(mark: BisectMark, revision: Option<&str>) -> Result<BisectResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    bisect_mark_in_repo(repo_dir, mark, revision)
+}
+
+pub fn bisect_reset() -> Result<BisectResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    bisect_reset_in_repo(repo_dir)
+}
+
+pub fn bisect_run(command: &[String]) -> Result<BisectResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    bisect_run_in_repo(repo_dir, command)
+}
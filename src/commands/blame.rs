@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diff::{diff_lines, is_binary, split_lines, DiffLine},
+    error::VcsError,
+    json_files::CommitData,
+    repo_file_manager::{
+        get_entry_kind, get_repo_dir, normalize_relative_path, strip_prefix, EntryKind,
+    },
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct BlameLine {
+    /// The commit which has last changed the line
+    pub commit_data: CommitData,
+    /// Line number starting from 1
+    pub line_number: usize,
+    /// Contents of the line with its line break
+    pub data: Vec<u8>,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum BlameResult {
+    Binary,
+    /// The line range is outside of the file
+    InvalidRange {
+        lines_count: usize,
+    },
+    Success {
+        lines: Vec<BlameLine>,
+    },
+}
+
+/// Parse a line range such as `10,20` or `10,` with numbers starting from 1
+pub fn parse_line_range(range: &str) -> Result<(usize, Option<usize>), String> {
+    let invalid = || format!("Invalid line range {}, expected <start>,<end>", range);
+    let (start, end) = range.split_once(',').ok_or_else(invalid)?;
+    let start: usize = start.parse().map_err(|_| invalid())?;
+    let end: Option<usize> = match end {
+        "" => None,
+        end => Some(end.parse().map_err(|_| invalid())?),
+    };
+    if start == 0 || end.is_some_and(|end| end < start) {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+/// Read the file at the commit if it is a file there
+fn read_commit_file(
+    vcs_state_manager: &VcsStateManager,
+    commit: &str,
+    path: &Path,
+) -> Result<Option<Vec<u8>>, VcsError> {
+    let full_path = vcs_state_manager.get_commit_dir(commit).join(path);
+    match get_entry_kind(&full_path) {
+        Ok(EntryKind::File { .. }) => Ok(Some(fs::read(full_path)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Attribute every line of the file at the revision to the commit which has last changed it
+///
+/// The history is walked from the newest commits to the oldest ones, lines which are
+/// equal to a line of a parent are passed to that parent, the rest belongs to the commit.
+pub fn blame_in_repo(
+    repo_dir: PathBuf,
+    path: &Path,
+    revision: &str,
+    range: Option<(usize, Option<usize>)>,
+) -> Result<BlameResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let commit = vcs_state_manager.resolve_revision(revision)?;
+    let not_found = || VcsError::PathNotFound {
+        revision: revision.to_string(),
+        path: path.to_path_buf(),
+    };
+    let path = normalize_relative_path(path).ok_or_else(not_found)?;
+    let data = read_commit_file(&vcs_state_manager, &commit, &path)?.ok_or_else(not_found)?;
+    if is_binary(&data) {
+        return Ok(BlameResult::Binary);
+    }
+    let lines = split_lines(&data);
+
+    let (start, end) = match range {
+        Some((start, end)) => (start, end.unwrap_or(lines.len())),
+        None => (1, lines.len()),
+    };
+    if range.is_some() && (start > lines.len() || end > lines.len()) {
+        return Ok(BlameResult::InvalidRange {
+            lines_count: lines.len(),
+        });
+    }
+
+    // Commits are processed in the reverse order of creation, so every commit comes after its children
+    let positions: HashMap<String, usize> = vcs_state_manager
+        .get_commit_list()?
+        .commits
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| (x.hash, i))
+        .collect();
+    let position = |commit: &str| {
+        positions
+            .get(commit)
+            .copied()
+            .ok_or_else(|| VcsError::UnknownRevision(commit.to_string()))
+    };
+
+    // Pending lines of every commit as pairs of the line index in the result and in the commit
+    let mut pending: BTreeMap<usize, (String, Vec<(usize, usize)>)> = BTreeMap::new();
+    let mut owners: Vec<String> = vec![String::new(); end + 1 - start];
+    pending.insert(
+        position(&commit)?,
+        (
+            commit,
+            (0..owners.len()).map(|x| (x, x + start - 1)).collect(),
+        ),
+    );
+
+    while let Some((_, (commit, mut lines_left))) = pending.pop_last() {
+        let commit_data = vcs_state_manager
+            .get_commit_data(&commit)?
+            .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+        let data = read_commit_file(&vcs_state_manager, &commit, &path)?.unwrap_or_default();
+        let commit_lines = split_lines(&data);
+
+        for parent in commit_data.parents.iter() {
+            if lines_left.is_empty() {
+                break;
+            }
+            let parent_data = match read_commit_file(&vcs_state_manager, parent, &path)? {
+                Some(parent_data) => parent_data,
+                None => continue,
+            };
+            let mut parent_lines: HashMap<usize, usize> = HashMap::new();
+            for diff_line in diff_lines(&split_lines(&parent_data), &commit_lines) {
+                if let DiffLine::Equal { old, new } = diff_line {
+                    parent_lines.insert(new, old);
+                }
+            }
+
+            let mut passed: Vec<(usize, usize)> = Vec::new();
+            lines_left.retain(
+                |(result_line, commit_line)| match parent_lines.get(commit_line) {
+                    Some(parent_line) => {
+                        passed.push((*result_line, *parent_line));
+                        false
+                    }
+                    None => true,
+                },
+            );
+            if !passed.is_empty() {
+                pending
+                    .entry(position(parent)?)
+                    .or_insert_with(|| (parent.clone(), Vec::new()))
+                    .1
+                    .extend(passed);
+            }
+        }
+
+        for (result_line, _) in lines_left {
+            owners[result_line] = commit.clone();
+        }
+    }
+
+    let mut commits: HashMap<String, CommitData> = HashMap::new();
+    let mut blame_lines: Vec<BlameLine> = Vec::new();
+    for (i, owner) in owners.into_iter().enumerate() {
+        if !commits.contains_key(&owner) {
+            let commit_data = vcs_state_manager
+                .get_commit_data(&owner)?
+                .ok_or_else(|| VcsError::UnknownRevision(owner.clone()))?;
+            commits.insert(owner.clone(), commit_data);
+        }
+        blame_lines.push(BlameLine {
+            commit_data: commits[&owner].clone(),
+            line_number: start + i,
+            data: lines[start + i - 1].to_vec(),
+        });
+    }
+    Ok(BlameResult::Success { lines: blame_lines })
+}
+
+/// Blame the file, the path is relative to the current folder
+pub fn blame(
+    path: &Path,
+    revision: &str,
+    range: Option<(usize, Option<usize>)>,
+) -> Result<BlameResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    let cur_dir = env::current_dir()?;
+    let relative_path = strip_prefix(&cur_dir.join(path), &repo_dir)?.to_path_buf();
+    blame_in_repo(repo_dir, &relative_path, revision, range)
+}
@@ -0,0 +1,222 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::VcsError,
+    json_files::SequencerData,
+    repo_file_manager::{get_contents, get_repo_dir, FileChange},
+    tree_merge::merge_trees,
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum CherryPickResult {
+    UncommitedChanges {
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    NotLastCommit,
+    /// Another cherry-pick or rebase has to be continued or aborted first
+    InProgress {
+        operation: String,
+    },
+    /// There is no cherry-pick to continue or abort
+    NotInProgress,
+    /// The working tree contains the conflicting files, resolve them and continue
+    MergeConflict {
+        commit: String,
+        paths: Vec<PathBuf>,
+    },
+    Success {
+        commits: Vec<String>,
+    },
+    Aborted {
+        commit: String,
+    },
+}
+
+/// Outcome of replaying the commits left in the sequencer
+pub(crate) enum ReplayResult {
+    Conflict { commit: String, paths: Vec<PathBuf> },
+    Done { commits: Vec<String> },
+}
+
+/// Commit the working tree with the message and author of the commit being replayed
+///
+/// Returns None if the working tree has no changes and the commit is skipped.
+fn commit_replayed(
+    vcs_state_manager: &mut VcsStateManager,
+    repo_dir: &Path,
+    sequencer: &SequencerData,
+    commit: &str,
+) -> Result<Option<String>, VcsError> {
+    let cur_commit = vcs_state_manager.get_commit()?;
+    if vcs_state_manager
+        .get_file_changes_commit(&cur_commit)?
+        .is_empty()
+    {
+        return Ok(None);
+    }
+
+    let commit_data = vcs_state_manager
+        .get_commit_data(commit)?
+        .ok_or_else(|| VcsError::UnknownRevision(commit.to_string()))?;
+    let contents = get_contents(repo_dir, true)?;
+    let new_commit = vcs_state_manager.commit_contents(
+        &commit_data.message,
+        &commit_data.author,
+        &sequencer.branch,
+        &contents,
+        vec![cur_commit],
+    )?;
+    Ok(Some(new_commit))
+}
+
+/// Replay the commits left in the sequencer on top of the current commit
+///
+/// If `resolved` is set, the first commit has been merged into the working
+/// tree already. On a conflict the sequencer is saved, otherwise it is removed.
+pub(crate) fn replay_commits(
+    vcs_state_manager: &mut VcsStateManager,
+    repo_dir: &Path,
+    mut sequencer: SequencerData,
+    mut resolved: bool,
+) -> Result<ReplayResult, VcsError> {
+    let mut commits: Vec<String> = Vec::new();
+    while !sequencer.todo.is_empty() {
+        let commit = sequencer.todo[0].clone();
+        if !resolved {
+            let commit_data = vcs_state_manager
+                .get_commit_data(&commit)?
+                .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+            let parent_dir = commit_data
+                .parents
+                .first()
+                .map(|parent| vcs_state_manager.get_commit_dir(parent));
+            let paths = merge_trees(
+                repo_dir,
+                parent_dir.as_deref(),
+                Some(&vcs_state_manager.get_commit_dir(&commit)),
+                "HEAD",
+                &commit,
+            )?;
+            if !paths.is_empty() {
+                vcs_state_manager.set_sequencer(Some(&sequencer))?;
+                return Ok(ReplayResult::Conflict { commit, paths });
+            }
+        }
+        resolved = false;
+
+        if let Some(new_commit) = commit_replayed(vcs_state_manager, repo_dir, &sequencer, &commit)?
+        {
+            commits.push(new_commit);
+        }
+        sequencer.todo.remove(0);
+    }
+
+    vcs_state_manager.set_sequencer(None)?;
+    Ok(ReplayResult::Done { commits })
+}
+
+/// Return the branch to the commit the interrupted operation has started from
+pub(crate) fn abort_sequencer(
+    vcs_state_manager: &mut VcsStateManager,
+    sequencer: &SequencerData,
+) -> Result<(), VcsError> {
+    let message = format!("{}: abort", sequencer.operation);
+    vcs_state_manager.set_branch_tip(&sequencer.branch, &sequencer.orig_commit, &message)?;
+    vcs_state_manager.jump_to_commit(&sequencer.orig_commit, Some(&sequencer.branch))?;
+    vcs_state_manager.set_sequencer(None)
+}
+
+impl From<ReplayResult> for CherryPickResult {
+    fn from(replay_result: ReplayResult) -> Self {
+        match replay_result {
+            ReplayResult::Conflict { commit, paths } => {
+                CherryPickResult::MergeConflict { commit, paths }
+            }
+            ReplayResult::Done { commits } => CherryPickResult::Success { commits },
+        }
+    }
+}
+
+/// Replay the changes of the given revisions on top of the current branch
+pub fn cherry_pick_in_repo(
+    repo_dir: PathBuf,
+    revisions: &[String],
+) -> Result<CherryPickResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    if let Some(sequencer) = vcs_state_manager.get_sequencer()? {
+        return Ok(CherryPickResult::InProgress {
+            operation: sequencer.operation,
+        });
+    }
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let branch = vcs_state_manager.get_branch()?;
+    let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
+    if !file_changes.is_empty() {
+        return Ok(CherryPickResult::UncommitedChanges { file_changes });
+    }
+    if vcs_state_manager.get_last_commit(&branch)? != cur_commit {
+        return Ok(CherryPickResult::NotLastCommit);
+    }
+
+    let mut todo: Vec<String> = Vec::new();
+    for revision in revisions {
+        todo.push(vcs_state_manager.resolve_revision(revision)?);
+    }
+    let sequencer = SequencerData {
+        operation: String::from("cherry-pick"),
+        branch,
+        orig_commit: cur_commit,
+        todo,
+    };
+    Ok(replay_commits(&mut vcs_state_manager, &repo_dir, sequencer, false)?.into())
+}
+
+/// Commit the resolved conflict and replay the remaining commits
+pub fn cherry_pick_continue_in_repo(repo_dir: PathBuf) -> Result<CherryPickResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    match vcs_state_manager.get_sequencer()? {
+        Some(sequencer) if sequencer.operation == "cherry-pick" => {
+            Ok(replay_commits(&mut vcs_state_manager, &repo_dir, sequencer, true)?.into())
+        }
+        Some(sequencer) => Ok(CherryPickResult::InProgress {
+            operation: sequencer.operation,
+        }),
+        None => Ok(CherryPickResult::NotInProgress),
+    }
+}
+
+/// Drop the picked commits and return to the commit the cherry-pick has started from
+pub fn cherry_pick_abort_in_repo(repo_dir: PathBuf) -> Result<CherryPickResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    match vcs_state_manager.get_sequencer()? {
+        Some(sequencer) if sequencer.operation == "cherry-pick" => {
+            abort_sequencer(&mut vcs_state_manager, &sequencer)?;
+            Ok(CherryPickResult::Aborted {
+                commit: sequencer.orig_commit,
+            })
+        }
+        Some(sequencer) => Ok(CherryPickResult::InProgress {
+            operation: sequencer.operation,
+        }),
+        None => Ok(CherryPickResult::NotInProgress),
+    }
+}
+
+pub fn cherry_pick(revisions: &[String]) -> Result<CherryPickResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    cherry_pick_in_repo(repo_dir, revisions)
+}
+
+pub fn cherry_pick_continue() -> Result<CherryPickResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    cherry_pick_continue_in_repo(repo_dir)
+}
+
+pub fn cherry_pick_abort() -> Result<CherryPickResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    cherry_pick_abort_in_repo(repo_dir)
+}
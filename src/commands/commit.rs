@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
 use crate::{
-    repo_file_manager::{get_repo_dir, FileChange},
+    error::VcsError,
+    repo_file_manager::{get_contents, get_repo_dir, FileChange},
     vcs_state_manager::VcsStateManager,
 };
 
@@ -13,18 +14,12 @@ pub struct CommitResult {
 }
 
 /// Commit current files in the repository
-pub fn commit_in_repo(repo_dir: PathBuf, message: &String) -> Result<CommitResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir);
+pub fn commit_in_repo(repo_dir: PathBuf, message: &str) -> Result<CommitResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
 
     let commit = vcs_state_manager.get_commit()?;
     let branch = vcs_state_manager.get_branch()?;
-    if *vcs_state_manager
-        .get_commits(&branch)?
-        .unwrap()
-        .last()
-        .unwrap()
-        != commit
-    {
+    if vcs_state_manager.get_last_commit(&branch)? != commit {
         Ok(CommitResult {
             successful: false,
             branch,
@@ -34,7 +29,7 @@ pub fn commit_in_repo(repo_dir: PathBuf, message: &String) -> Result<CommitResul
     } else {
         let file_changes = vcs_state_manager.get_file_changes_commit(&commit)?;
         if !file_changes.is_empty() {
-            let new_commit = vcs_state_manager.commit(&message, &branch)?;
+            let new_commit = vcs_state_manager.commit(message, &branch)?;
             Ok(CommitResult {
                 successful: true,
                 branch,
@@ -52,7 +47,53 @@ pub fn commit_in_repo(repo_dir: PathBuf, message: &String) -> Result<CommitResul
     }
 }
 
-pub fn commit(message: &String) -> Result<CommitResult, std::io::Error> {
+/// Replace the last commit of the branch with a commit of the current files
+///
+/// The parents and the author are kept, the message is kept unless a new one is given.
+pub fn amend_in_repo(repo_dir: PathBuf, message: Option<&str>) -> Result<CommitResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let commit = vcs_state_manager.get_commit()?;
+    let branch = vcs_state_manager.get_branch()?;
+    if vcs_state_manager.get_last_commit(&branch)? != commit {
+        return Ok(CommitResult {
+            successful: false,
+            branch,
+            commit,
+            file_changes: Vec::new(),
+        });
+    }
+
+    let commit_data = vcs_state_manager
+        .get_commit_data(&commit)?
+        .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+    let message = message.unwrap_or(&commit_data.message);
+    let file_changes = vcs_state_manager.get_file_changes_commit(&commit)?;
+    let contents = get_contents(&repo_dir, true)?;
+    let new_commit = vcs_state_manager.create_commit(
+        message,
+        &commit_data.author,
+        &contents,
+        commit_data.parents.clone(),
+    )?;
+
+    let reflog_message = format!("commit (amend): {}", message);
+    vcs_state_manager.set_branch_tip(&branch, &new_commit, &reflog_message)?;
+    vcs_state_manager.set_commit(&new_commit, &reflog_message)?;
+    Ok(CommitResult {
+        successful: true,
+        branch,
+        commit: new_commit,
+        file_changes,
+    })
+}
+
+pub fn commit(message: &str) -> Result<CommitResult, VcsError> {
     let repo_dir = get_repo_dir()?;
     commit_in_repo(repo_dir, message)
 }
+
+pub fn amend(message: Option<&str>) -> Result<CommitResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    amend_in_repo(repo_dir, message)
+}
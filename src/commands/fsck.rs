@@ -0,0 +1,250 @@
+use std::{collections::HashSet, fs, iter, path::PathBuf};
+
+use crate::{
+    error::VcsError,
+    repo_file_manager::{get_commit_hash, get_contents_hash, get_repo_dir},
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum FsckProblem {
+    /// The snapshot contents do not match the hash recorded in commit_list.json
+    CorruptSnapshot { commit: String },
+    /// The commit hash does not match its metadata in commit_list.json
+    CorruptCommit { commit: String },
+    /// The snapshot is not listed in commit_list.json
+    DanglingSnapshot { commit: String },
+    /// The commit is listed in commit_list.json but its snapshot is missing
+    MissingSnapshot { commit: String },
+    /// The commit refers to a parent which is not listed in commit_list.json
+    InvalidParent { commit: String, parent: String },
+    /// The branch refers to a commit which is not listed in commit_list.json
+    MissingCommit { branch: String, commit: String },
+    /// stash_list.json refers to a commit which is not listed in commit_list.json
+    InvalidStashCommit { commit: String },
+    /// sequencer.json refers to a commit which is not listed in commit_list.json
+    InvalidSequencerCommit { commit: String },
+    /// bisect.json refers to a commit which is not listed in commit_list.json
+    InvalidBisectCommit { commit: String },
+    /// state.json refers to a commit which is not listed in commit_list.json
+    InvalidStateCommit { commit: String },
+    /// state.json refers to a branch which is not listed in branch_list.json
+    InvalidStateBranch { branch: String },
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct FsckResult {
+    /// Found problems and whether each of them has been repaired
+    pub problems: Vec<(FsckProblem, bool)>,
+}
+
+/// Check the integrity of the repository and optionally repair it
+///
+/// Repositories of older formats are refused when they are opened, so hashes
+/// are always compared with the scheme they have been created with. Problems
+/// which would need the history to be rewritten, such as corrupt commits or
+/// invalid parents, are only reported.
+pub fn fsck_in_repo(repo_dir: PathBuf, repair: bool) -> Result<FsckResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+    let hash_algorithm = vcs_state_manager.get_hash_algorithm()?;
+
+    let mut commit_list = vcs_state_manager.get_commit_list()?;
+    let mut branch_list = vcs_state_manager.get_branch_list()?;
+    let mut stash_list = vcs_state_manager.get_stash_list()?;
+    let mut problems: Vec<(FsckProblem, bool)> = Vec::new();
+
+    let mut snapshots: Vec<String> = Vec::new();
+    for entry in fs::read_dir(repo_dir.join(".vcs").join("commits"))? {
+        snapshots.push(entry?.file_name().to_string_lossy().to_string());
+    }
+    snapshots.sort();
+
+    for snapshot in snapshots {
+        match commit_list.commits.iter().find(|x| x.hash == snapshot) {
+            Some(commit_data) => {
+                let contents = vcs_state_manager.get_commit_contents(&snapshot)?;
+                if commit_data.tree != get_contents_hash(&repo_dir, &contents)? {
+                    problems.push((FsckProblem::CorruptSnapshot { commit: snapshot }, false));
+                }
+            }
+            // The metadata of the commit is lost, so it can not be recovered
+            None => problems.push((FsckProblem::DanglingSnapshot { commit: snapshot }, false)),
+        }
+    }
+
+    for commit_data in commit_list.commits.iter() {
+        let hash = get_commit_hash(
+            hash_algorithm,
+            &commit_data.tree,
+            &commit_data.parents,
+            &commit_data.message,
+            &commit_data.author,
+            commit_data.date,
+        );
+        if hash != commit_data.hash {
+            problems.push((
+                FsckProblem::CorruptCommit {
+                    commit: commit_data.hash.clone(),
+                },
+                false,
+            ));
+        }
+    }
+
+    commit_list.commits.retain(|commit_data| {
+        if vcs_state_manager.get_commit_dir(&commit_data.hash).is_dir() {
+            return true;
+        }
+        problems.push((
+            FsckProblem::MissingSnapshot {
+                commit: commit_data.hash.clone(),
+            },
+            repair,
+        ));
+        !repair
+    });
+    let commits: HashSet<String> = commit_list.commits.iter().map(|x| x.hash.clone()).collect();
+
+    for commit_data in commit_list.commits.iter() {
+        for parent in commit_data.parents.iter() {
+            if !commits.contains(parent) {
+                problems.push((
+                    FsckProblem::InvalidParent {
+                        commit: commit_data.hash.clone(),
+                        parent: parent.clone(),
+                    },
+                    false,
+                ));
+            }
+        }
+    }
+
+    // Branches are first parent chains, so they are cut at the first missing commit
+    for branch_data in branch_list.branches.iter_mut() {
+        for commit in branch_data.commits.iter() {
+            if !commits.contains(commit) {
+                problems.push((
+                    FsckProblem::MissingCommit {
+                        branch: branch_data.name.clone(),
+                        commit: commit.clone(),
+                    },
+                    repair,
+                ));
+            }
+        }
+        if repair {
+            if let Some(position) = branch_data
+                .commits
+                .iter()
+                .position(|x| !commits.contains(x))
+            {
+                branch_data.commits.truncate(position);
+            }
+        }
+    }
+    branch_list
+        .branches
+        .retain(|branch_data| !branch_data.commits.is_empty());
+
+    stash_list.stashes.retain(|stash_data| {
+        if commits.contains(&stash_data.commit) {
+            return true;
+        }
+        problems.push((
+            FsckProblem::InvalidStashCommit {
+                commit: stash_data.commit.clone(),
+            },
+            repair,
+        ));
+        !repair
+    });
+
+    // Interrupted operations can not go on without their commits, so they are dropped
+    if let Some(sequencer) = vcs_state_manager.get_sequencer()? {
+        let missing: Vec<&String> = iter::once(&sequencer.orig_commit)
+            .chain(sequencer.todo.iter())
+            .filter(|x| !commits.contains(*x))
+            .collect();
+        for commit in missing.iter() {
+            problems.push((
+                FsckProblem::InvalidSequencerCommit {
+                    commit: commit.to_string(),
+                },
+                repair,
+            ));
+        }
+        if repair && !missing.is_empty() {
+            vcs_state_manager.set_sequencer(None)?;
+        }
+    }
+    if let Some(bisect) = vcs_state_manager.get_bisect()? {
+        let missing: Vec<&String> = iter::once(&bisect.orig_commit)
+            .chain(bisect.bad.iter())
+            .chain(bisect.good.iter())
+            .chain(bisect.skipped.iter())
+            .filter(|x| !commits.contains(*x))
+            .collect();
+        for commit in missing.iter() {
+            problems.push((
+                FsckProblem::InvalidBisectCommit {
+                    commit: commit.to_string(),
+                },
+                repair,
+            ));
+        }
+        if repair && !missing.is_empty() {
+            vcs_state_manager.set_bisect(None)?;
+        }
+    }
+
+    let cur_branch = vcs_state_manager.get_branch()?;
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let branch_exists = branch_list.branches.iter().any(|x| x.name == cur_branch);
+    let commit_exists = commits.contains(&cur_commit);
+
+    if repair {
+        vcs_state_manager.set_commit_list(commit_list)?;
+        vcs_state_manager.set_branch_list(branch_list)?;
+        vcs_state_manager.set_stash_list(stash_list)?;
+    }
+
+    if !commit_exists {
+        let mut repaired = false;
+        if repair {
+            let branch = if branch_exists { &cur_branch } else { "master" };
+            if let Some(commits) = vcs_state_manager.get_commits(branch)? {
+                if let Some(commit) = commits.last() {
+                    vcs_state_manager.set_commit(commit, "fsck: repair current commit")?;
+                    repaired = true;
+                }
+            }
+        }
+        problems.push((
+            FsckProblem::InvalidStateCommit { commit: cur_commit },
+            repaired,
+        ));
+    }
+
+    if !branch_exists {
+        let mut repaired = false;
+        if repair {
+            let commit = vcs_state_manager.get_commit()?;
+            let branch = vcs_state_manager
+                .get_branch_with_commit(&commit, Some("master"))?
+                .unwrap_or_else(|| String::from("master"));
+            vcs_state_manager.set_branch(&branch)?;
+            repaired = true;
+        }
+        problems.push((
+            FsckProblem::InvalidStateBranch { branch: cur_branch },
+            repaired,
+        ));
+    }
+
+    Ok(FsckResult { problems })
+}
+
+pub fn fsck(repair: bool) -> Result<FsckResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    fsck_in_repo(repo_dir, repair)
+}
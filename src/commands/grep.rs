@@ -0,0 +1,149 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::{
+    diff::{is_binary, split_lines},
+    error::VcsError,
+    repo_file_manager::{
+        get_contents, get_entry_kind, get_repo_dir, normalize_relative_path, strip_prefix,
+        EntryKind,
+    },
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum GrepMatch {
+    Line {
+        /// The revision as it was given, None for the working tree
+        revision: Option<String>,
+        /// Path relative to the repository
+        path: PathBuf,
+        /// Line number starting from 1
+        line_number: usize,
+        /// Contents of the line without its line break
+        line: Vec<u8>,
+    },
+    /// A binary file matches, its lines are not reported
+    Binary {
+        revision: Option<String>,
+        path: PathBuf,
+    },
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct GrepOptions {
+    pub ignore_case: bool,
+    /// Paths relative to the repository, only files inside them are searched
+    pub paths: Vec<PathBuf>,
+}
+
+pub struct GrepResult {
+    pub matches: Vec<GrepMatch>,
+}
+
+/// Search lines of the files in the folder, which is either the working tree or a commit
+fn grep_dir(
+    dir: &Path,
+    ignore_vcs: bool,
+    revision: Option<&str>,
+    regex: &Regex,
+    options: &GrepOptions,
+    matches: &mut Vec<GrepMatch>,
+) -> Result<(), VcsError> {
+    let mut files: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for entry in get_contents(dir, ignore_vcs)? {
+        if !matches!(get_entry_kind(&entry)?, EntryKind::File { .. }) {
+            continue;
+        }
+        let relative_path = strip_prefix(&entry, dir)?.to_path_buf();
+        if options.paths.is_empty() || options.paths.iter().any(|x| relative_path.starts_with(x)) {
+            files.push((relative_path, entry));
+        }
+    }
+    files.sort();
+
+    for (path, entry) in files {
+        let data = fs::read(entry)?;
+        if is_binary(&data) {
+            if regex.is_match(&data) {
+                matches.push(GrepMatch::Binary {
+                    revision: revision.map(String::from),
+                    path,
+                });
+            }
+            continue;
+        }
+        for (i, line) in split_lines(&data).into_iter().enumerate() {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            if regex.is_match(line) {
+                matches.push(GrepMatch::Line {
+                    revision: revision.map(String::from),
+                    path: path.clone(),
+                    line_number: i + 1,
+                    line: line.to_vec(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Search the regular expression in the working tree or in the snapshots of the revisions
+///
+/// Commits are searched directly in their snapshots, the working tree is not changed.
+pub fn grep_in_repo(
+    repo_dir: PathBuf,
+    pattern: &str,
+    revisions: &[String],
+    options: &GrepOptions,
+) -> Result<GrepResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|error| VcsError::InvalidPattern(error.to_string()))?;
+
+    let mut matches: Vec<GrepMatch> = Vec::new();
+    if revisions.is_empty() {
+        grep_dir(&repo_dir, true, None, &regex, options, &mut matches)?;
+    }
+    for revision in revisions {
+        let commit = vcs_state_manager.resolve_revision(revision)?;
+        let commit_dir = vcs_state_manager.get_commit_dir(&commit);
+        grep_dir(
+            &commit_dir,
+            false,
+            Some(revision),
+            &regex,
+            options,
+            &mut matches,
+        )?;
+    }
+    Ok(GrepResult { matches })
+}
+
+/// Search the pattern, the paths in the options are relative to the current folder
+pub fn grep(
+    pattern: &str,
+    revisions: &[String],
+    options: &GrepOptions,
+) -> Result<GrepResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    let cur_dir = env::current_dir()?;
+    let mut options = options.clone();
+    for path in options.paths.iter_mut() {
+        let full_path = cur_dir.join(&path);
+        *path = normalize_relative_path(strip_prefix(&full_path, &repo_dir)?).ok_or_else(|| {
+            VcsError::PathNotFound {
+                revision: String::from("HEAD"),
+                path: path.clone(),
+            }
+        })?;
+    }
+    grep_in_repo(repo_dir, pattern, revisions, &options)
+}
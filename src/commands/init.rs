@@ -1,9 +1,14 @@
 use std::path::PathBuf;
 
-use crate::vcs_state_manager::VcsStateManager;
+use crate::{error::VcsError, hash::HashAlgorithm, vcs_state_manager::VcsStateManager};
 
 /// Initialize an empty repository
-pub fn init(repo_path: PathBuf) -> Result<String, std::io::Error> {
+pub fn init(repo_path: PathBuf) -> Result<String, VcsError> {
+    init_with_hash(repo_path, HashAlgorithm::default())
+}
+
+/// Initialize an empty repository whose hashes use the given algorithm
+pub fn init_with_hash(repo_path: PathBuf, hash: HashAlgorithm) -> Result<String, VcsError> {
     let mut vcs_state_manager = VcsStateManager::init(repo_path);
-    vcs_state_manager.init_repository()
+    vcs_state_manager.init_repository(hash)
 }
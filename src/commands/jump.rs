@@ -1,7 +1,9 @@
 use std::path::PathBuf;
 
 use crate::{
+    error::VcsError,
     repo_file_manager::{get_repo_dir, FileChange},
+    tree_merge::plan_merge,
     vcs_state_manager::VcsStateManager,
 };
 
@@ -11,57 +13,109 @@ pub enum JumpResult {
         file_changes: Vec<(FileChange, PathBuf)>,
     },
     NotFound,
+    /// Local changes conflict with the difference between the commits, nothing has been changed
+    MergeConflict {
+        paths: Vec<PathBuf>,
+    },
     Success {
         commit: String,
         branch: String,
     },
 }
 
-/// Jump to the given commit
+/// What to do with local changes when jumping
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum JumpMode {
+    /// Refuse to jump if there are local changes
+    Safe,
+    /// Discard local changes
+    Force,
+    /// Carry local changes over to the new commit if they do not conflict
+    Merge,
+}
+
+/// Jump to the given commit, any revision such as `@{1}` is accepted
 pub fn jump_commit_in_repo(
     repo_dir: PathBuf,
-    commit: &String,
-    branch_priority: Option<&String>,
-) -> Result<JumpResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir);
+    commit: &str,
+    branch_priority: Option<&str>,
+) -> Result<JumpResult, VcsError> {
+    jump_commit_with_mode_in_repo(repo_dir, commit, branch_priority, JumpMode::Safe)
+}
+
+/// Jump to the given commit handling local changes according to the mode
+pub fn jump_commit_with_mode_in_repo(
+    repo_dir: PathBuf,
+    commit: &str,
+    branch_priority: Option<&str>,
+    mode: JumpMode,
+) -> Result<JumpResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
 
     let cur_commit = vcs_state_manager.get_commit()?;
     let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
-    if !file_changes.is_empty() {
-        Ok(JumpResult::UncommitedChanges { file_changes })
+    if !file_changes.is_empty() && mode == JumpMode::Safe {
+        return Ok(JumpResult::UncommitedChanges { file_changes });
+    }
+
+    let commit = match vcs_state_manager.resolve_revision(commit) {
+        Ok(commit) => commit,
+        Err(VcsError::UnknownRevision(_)) => return Ok(JumpResult::NotFound),
+        Err(error) => return Err(error),
+    };
+    if file_changes.is_empty() || mode == JumpMode::Force {
+        vcs_state_manager.jump_to_commit(&commit, branch_priority)?;
     } else {
-        match vcs_state_manager.get_commit_data(commit)? {
-            Some(_commit_data) => {
-                vcs_state_manager.jump_to_commit(&commit, branch_priority)?;
-                Ok(JumpResult::Success {
-                    commit: vcs_state_manager.get_commit()?,
-                    branch: vcs_state_manager.get_branch()?,
-                })
-            }
-            None => Ok(JumpResult::NotFound),
+        let plan = plan_merge(
+            &repo_dir,
+            Some(&vcs_state_manager.get_commit_dir(&cur_commit)),
+            Some(&vcs_state_manager.get_commit_dir(&commit)),
+            "local",
+            &commit,
+        )?;
+        if !plan.conflicts.is_empty() {
+            return Ok(JumpResult::MergeConflict {
+                paths: plan.conflicts,
+            });
         }
+        plan.apply(&repo_dir)?;
+        vcs_state_manager.move_head(&commit, branch_priority)?;
     }
+
+    Ok(JumpResult::Success {
+        commit: vcs_state_manager.get_commit()?,
+        branch: vcs_state_manager.get_branch()?,
+    })
 }
 
 /// Jump to the last commit of the given branch
-pub fn jump_branch_in_repo(
+pub fn jump_branch_in_repo(repo_dir: PathBuf, branch: &str) -> Result<JumpResult, VcsError> {
+    jump_branch_with_mode_in_repo(repo_dir, branch, JumpMode::Safe)
+}
+
+/// Jump to the last commit of the given branch handling local changes according to the mode
+pub fn jump_branch_with_mode_in_repo(
     repo_dir: PathBuf,
-    branch: &String,
-) -> Result<JumpResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir.clone());
+    branch: &str,
+    mode: JumpMode,
+) -> Result<JumpResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
 
     match vcs_state_manager.get_commits(branch)? {
-        Some(commits) => jump_commit_in_repo(repo_dir, commits.last().unwrap(), Some(&branch)),
+        Some(commits) => match commits.last() {
+            Some(commit) => jump_commit_with_mode_in_repo(repo_dir, commit, Some(branch), mode),
+            None => Ok(JumpResult::NotFound),
+        },
         None => Ok(JumpResult::NotFound),
     }
 }
 
-pub fn jump_commit(commit: &String) -> Result<JumpResult, std::io::Error> {
+pub fn jump_commit(commit: &str, mode: JumpMode) -> Result<JumpResult, VcsError> {
     let repo_dir = get_repo_dir()?;
-    jump_commit_in_repo(repo_dir, commit, None)
+    jump_commit_with_mode_in_repo(repo_dir, commit, None, mode)
 }
 
-pub fn jump_branch(branch: &String) -> Result<JumpResult, std::io::Error> {
+pub fn jump_branch(branch: &str, mode: JumpMode) -> Result<JumpResult, VcsError> {
     let repo_dir = get_repo_dir()?;
-    jump_branch_in_repo(repo_dir, branch)
+    jump_branch_with_mode_in_repo(repo_dir, branch, mode)
 }
@@ -0,0 +1,173 @@
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use crate::{
+    error::VcsError,
+    json_files::{get_large_files_config, set_large_files_config, LargeFilesConfig},
+    large_objects::{LargeObjects, Pointer},
+    repo_file_manager::{get_repo_dir, strip_prefix},
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct LargeFile {
+    /// Path relative to the repository
+    pub path: PathBuf,
+    pub pointer: Pointer,
+    /// The object is stored in the large object area, it has not been pruned
+    pub present: bool,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum LfsResult {
+    Config {
+        config: LargeFilesConfig,
+    },
+    /// The pattern is not tracked, so it can not be untracked
+    NotTracked,
+    List {
+        files: Vec<LargeFile>,
+    },
+    Pruned {
+        objects: usize,
+        bytes: u64,
+    },
+}
+
+/// Open the repository and change its large files configuration
+fn update_config(
+    repo_dir: PathBuf,
+    update: impl FnOnce(&mut LargeFilesConfig) -> bool,
+) -> Result<LfsResult, VcsError> {
+    VcsStateManager::open(repo_dir.clone())?;
+
+    let mut config = get_large_files_config(&repo_dir)?;
+    if !update(&mut config) {
+        return Ok(LfsResult::NotTracked);
+    }
+    // Invalid patterns are rejected before they are saved
+    LargeObjects::with_config(&repo_dir, &config)?;
+    set_large_files_config(&repo_dir, &config)?;
+    Ok(LfsResult::Config { config })
+}
+
+/// Store files matching the glob pattern as large objects from the next commit on
+pub fn lfs_track_in_repo(repo_dir: PathBuf, pattern: &str) -> Result<LfsResult, VcsError> {
+    update_config(repo_dir, |config| {
+        if !config.patterns.iter().any(|x| x == pattern) {
+            config.patterns.push(pattern.to_string());
+        }
+        true
+    })
+}
+
+/// Stop storing files matching the pattern as large objects, committed pointers are kept
+pub fn lfs_untrack_in_repo(repo_dir: PathBuf, pattern: &str) -> Result<LfsResult, VcsError> {
+    update_config(repo_dir, |config| {
+        let patterns_count = config.patterns.len();
+        config.patterns.retain(|x| x != pattern);
+        config.patterns.len() != patterns_count
+    })
+}
+
+/// Store binary files larger than the threshold as large objects, None disables the threshold
+pub fn lfs_threshold_in_repo(
+    repo_dir: PathBuf,
+    threshold: Option<u64>,
+) -> Result<LfsResult, VcsError> {
+    update_config(repo_dir, |config| {
+        config.threshold = threshold;
+        true
+    })
+}
+
+/// List the files of the revision which are stored as large objects
+pub fn lfs_list_in_repo(repo_dir: PathBuf, revision: &str) -> Result<LfsResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+    let large_objects = LargeObjects::open(&repo_dir)?;
+
+    let commit = vcs_state_manager.resolve_revision(revision)?;
+    let commit_dir = vcs_state_manager.get_commit_dir(&commit);
+    let mut files: Vec<LargeFile> = Vec::new();
+    for entry in vcs_state_manager.get_commit_contents(&commit)? {
+        if let Some(pointer) = Pointer::read(&entry)? {
+            files.push(LargeFile {
+                path: strip_prefix(&entry, &commit_dir)?.to_path_buf(),
+                present: large_objects.get_object_path(&pointer.hash).is_file(),
+                pointer,
+            });
+        }
+    }
+    files.sort_by(|x, y| x.path.cmp(&y.path));
+    Ok(LfsResult::List { files })
+}
+
+/// Remove large objects which are not used by HEAD, the last commits of branches or stashes
+///
+/// Commits keep their pointers, jumping to an older commit leaves pointer files
+/// in place of pruned objects.
+pub fn lfs_prune_in_repo(repo_dir: PathBuf) -> Result<LfsResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+    let large_objects = LargeObjects::open(&repo_dir)?;
+
+    let mut commits: Vec<String> = vec![vcs_state_manager.get_commit()?];
+    for branch_data in vcs_state_manager.get_branch_list()?.branches {
+        commits.push(vcs_state_manager.get_last_commit(&branch_data.name)?);
+    }
+    for stash_data in vcs_state_manager.get_stash_list()?.stashes {
+        commits.push(stash_data.commit);
+    }
+    if let Some(sequencer) = vcs_state_manager.get_sequencer()? {
+        commits.push(sequencer.orig_commit);
+    }
+    if let Some(bisect) = vcs_state_manager.get_bisect()? {
+        commits.push(bisect.orig_commit);
+    }
+
+    let mut used: HashSet<String> = HashSet::new();
+    for commit in commits {
+        for entry in vcs_state_manager.get_commit_contents(&commit)? {
+            if let Some(pointer) = Pointer::read(&entry)? {
+                used.insert(pointer.hash);
+            }
+        }
+    }
+
+    let (mut objects, mut bytes) = (0, 0);
+    let objects_dir = large_objects.get_objects_dir();
+    if objects_dir.is_dir() {
+        for entry in fs::read_dir(objects_dir)? {
+            let entry = entry?;
+            if !used.contains(entry.file_name().to_string_lossy().as_ref()) {
+                bytes += entry.metadata()?.len();
+                objects += 1;
+                fs::remove_file(entry.path())?;
+            }
+        }
+    }
+    Ok(LfsResult::Pruned { objects, bytes })
+}
+
+pub fn lfs_track(pattern: &str) -> Result<LfsResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    lfs_track_in_repo(repo_dir, pattern)
+}
+
+pub fn lfs_untrack(pattern: &str) -> Result<LfsResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    lfs_untrack_in_repo(repo_dir, pattern)
+}
+
+pub fn lfs_threshold(threshold: Option<u64>) -> Result<LfsResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    lfs_threshold_in_repo(repo_dir, threshold)
+}
+
+pub fn lfs_list(revision: &str) -> Result<LfsResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    lfs_list_in_repo(repo_dir, revision)
+}
+
+pub fn lfs_prune() -> Result<LfsResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    lfs_prune_in_repo(repo_dir)
+}
@@ -1,47 +1,300 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
 
 use crate::{
+    error::VcsError,
     json_files::CommitData,
-    repo_file_manager::{get_file_changes, get_repo_dir, FileChange},
+    repo_file_manager::{
+        get_file_changes, get_repo_dir, normalize_relative_path, strip_prefix, FileChange,
+    },
     vcs_state_manager::VcsStateManager,
 };
 
 pub struct LogResult {
     pub commit_list: Vec<(CommitData, Vec<(FileChange, PathBuf)>)>,
+    /// Names pointing to commits such as `HEAD -> master` or branch names
+    pub decorations: HashMap<String, Vec<String>>,
+}
+
+/// Filters for the logged commits, the default logs the history of the current commit
+#[derive(Default, Debug, Clone)]
+pub struct LogOptions {
+    pub max_count: Option<usize>,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    /// Part of the author name
+    pub author: Option<String>,
+    /// Part of the commit message
+    pub grep: Option<String>,
+    /// Paths relative to the repository, only commits changing them are logged
+    pub paths: Vec<PathBuf>,
+    /// Log commits of all branches instead of the current history
+    pub all: bool,
+    /// Leave the changes of the commits empty unless they are needed for path filters
+    pub skip_changes: bool,
+}
+
+impl LogOptions {
+    /// Check the filters which only need commit metadata
+    fn matches_metadata(&self, commit_data: &CommitData) -> bool {
+        self.since.is_none_or(|since| commit_data.date >= since)
+            && self.until.is_none_or(|until| commit_data.date <= until)
+            && self
+                .author
+                .as_ref()
+                .is_none_or(|author| commit_data.author.contains(author.as_str()))
+            && self
+                .grep
+                .as_ref()
+                .is_none_or(|grep| commit_data.message.contains(grep.as_str()))
+    }
+
+    /// Check if any of the changed paths is inside of the filtered paths
+    ///
+    /// Renamed and copied files also match by the path they come from.
+    fn matches_paths(
+        &self,
+        commit_dir: &Path,
+        file_changes: &[(FileChange, PathBuf)],
+    ) -> Result<bool, VcsError> {
+        for (file_change, path) in file_changes {
+            let mut paths = vec![path];
+            if let FileChange::Renamed { from, .. } | FileChange::Copied { from, .. } = file_change
+            {
+                paths.push(from);
+            }
+            for path in paths {
+                let path = strip_prefix(path, commit_dir)?;
+                if self.paths.iter().any(|x| path.starts_with(x)) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Parse a date such as `2022-10-15`, `2022-10-15 12:00:00`, RFC 3339 or `3 days ago`
+pub fn parse_date(date: &str) -> Result<SystemTime, String> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(date) {
+        return Ok(date_time.into());
+    }
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S") {
+        if let Some(date_time) = Local.from_local_datetime(&date_time).earliest() {
+            return Ok(date_time.into());
+        }
+    }
+    if let Some(date_time) = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .and_then(|x| x.and_hms_opt(0, 0, 0))
+    {
+        if let Some(date_time) = Local.from_local_datetime(&date_time).earliest() {
+            return Ok(date_time.into());
+        }
+    }
+
+    let words: Vec<&str> = date.split_whitespace().collect();
+    if let [count, unit, "ago"] = words[..] {
+        let count: u64 = count
+            .parse()
+            .map_err(|_| format!("Invalid date {}", date))?;
+        let seconds = match unit.trim_end_matches('s') {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 60 * 60,
+            "day" => 24 * 60 * 60,
+            "week" => 7 * 24 * 60 * 60,
+            _ => return Err(format!("Invalid date {}", date)),
+        };
+        return Ok(SystemTime::now() - Duration::from_secs(count * seconds));
+    }
+    Err(format!("Invalid date {}", date))
+}
+
+/// Expand placeholders of a `--format` template
+///
+/// Supported placeholders are `%H` and `%h` for the full and short hash, `%P`
+/// for parents, `%an` for the author, `%ad` for the date, `%s` for the first
+/// line of the message, `%B` for the whole message, `%n` and `%%`.
+pub fn format_commit(template: &str, commit_data: &CommitData) -> String {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            result.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('H') => result.push_str(&commit_data.hash),
+            Some('h') => result.push_str(&commit_data.hash[..commit_data.hash.len().min(7)]),
+            Some('P') => result.push_str(&commit_data.parents.join(" ")),
+            Some('s') => result.push_str(commit_data.message.lines().next().unwrap_or_default()),
+            Some('B') => result.push_str(&commit_data.message),
+            Some('n') => result.push('\n'),
+            Some('%') => result.push('%'),
+            Some('a') if chars.next_if_eq(&'n').is_some() => result.push_str(&commit_data.author),
+            Some('a') if chars.next_if_eq(&'d').is_some() => {
+                result.push_str(&DateTime::<Local>::from(commit_data.date).to_rfc2822())
+            }
+            Some(other) => {
+                result.push('%');
+                result.push(other);
+            }
+            None => result.push('%'),
+        }
+    }
+    result
+}
+
+/// Get commits reachable from any branch with the newest ones first
+fn get_all_commits(vcs_state_manager: &mut VcsStateManager) -> Result<Vec<String>, VcsError> {
+    let mut reachable: HashSet<String> = HashSet::new();
+    let mut tips = vec![vcs_state_manager.get_commit()?];
+    for branch_data in vcs_state_manager.get_branch_list()?.branches {
+        tips.extend(branch_data.commits.last().cloned());
+    }
+    for tip in tips {
+        if !reachable.contains(&tip) {
+            reachable.extend(vcs_state_manager.get_ancestors(&tip)?);
+        }
+    }
+
+    let mut commits: Vec<String> = vcs_state_manager
+        .get_commit_list()?
+        .commits
+        .into_iter()
+        .map(|x| x.hash)
+        .filter(|x| reachable.contains(x))
+        .collect();
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Get HEAD and the branch names pointing to every commit
+fn get_decorations(
+    vcs_state_manager: &mut VcsStateManager,
+) -> Result<HashMap<String, Vec<String>>, VcsError> {
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let cur_branch = vcs_state_manager.get_branch()?;
+
+    let mut decorations: HashMap<String, Vec<String>> = HashMap::new();
+    let mut head_attached = false;
+    for branch_data in vcs_state_manager.get_branch_list()?.branches {
+        if let Some(commit) = branch_data.commits.last() {
+            let name = if branch_data.name == cur_branch && *commit == cur_commit {
+                head_attached = true;
+                format!("HEAD -> {}", branch_data.name)
+            } else {
+                branch_data.name
+            };
+            decorations.entry(commit.clone()).or_default().push(name);
+        }
+    }
+    if !head_attached {
+        decorations
+            .entry(cur_commit)
+            .or_default()
+            .push(String::from("HEAD"));
+    }
+    for names in decorations.values_mut() {
+        names.sort_by_key(|x| !x.starts_with("HEAD"));
+    }
+    Ok(decorations)
+}
+
+/// Get the changes made by the commit relative to its first parent
+fn get_commit_changes(
+    vcs_state_manager: &mut VcsStateManager,
+    commit_data: &CommitData,
+) -> Result<Vec<(FileChange, PathBuf)>, VcsError> {
+    let commit_dir = vcs_state_manager.get_commit_dir(&commit_data.hash);
+    // A commit without parents is compared to an empty tree, so all its files are added
+    let (parent_dir, parent_contents) = match commit_data.parents.first() {
+        Some(parent) => (
+            vcs_state_manager.get_commit_dir(parent),
+            vcs_state_manager.get_commit_contents(parent)?,
+        ),
+        None => (commit_dir.clone(), vec![commit_dir.clone()]),
+    };
+    get_file_changes(
+        vcs_state_manager.get_hash_algorithm()?,
+        &commit_dir,
+        &vcs_state_manager.get_commit_contents(&commit_data.hash)?,
+        &parent_dir,
+        &parent_contents,
+    )
 }
 
 /// Log commits history until the current one
-pub fn log_in_repo(repo_dir: PathBuf) -> Result<LogResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir);
+pub fn log_in_repo(repo_dir: PathBuf) -> Result<LogResult, VcsError> {
+    log_with_options_in_repo(repo_dir, &LogOptions::default())
+}
+
+/// Log commits matching the options, the newest ones first
+///
+/// Metadata filters are checked before the changes of a commit are computed,
+/// changes are only computed if they are returned or filtered by paths.
+pub fn log_with_options_in_repo(
+    repo_dir: PathBuf,
+    options: &LogOptions,
+) -> Result<LogResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
 
-    let commit_history = vcs_state_manager.get_commit_history()?;
+    let commits = if options.all {
+        get_all_commits(&mut vcs_state_manager)?
+    } else {
+        vcs_state_manager.get_commit_history()?
+    };
 
     let mut log_result = LogResult {
         commit_list: Vec::new(),
+        decorations: get_decorations(&mut vcs_state_manager)?,
     };
-    let mut prev_commit: Option<String> = None;
-    for commit in commit_history {
-        if prev_commit.is_some() {
-            let prev_commit_value = prev_commit.unwrap();
-            let prev_file_changes = get_file_changes(
-                &vcs_state_manager.get_commit_dir(&prev_commit_value),
-                &vcs_state_manager.get_commit_contents(&prev_commit_value)?,
-                &vcs_state_manager.get_commit_dir(&commit),
-                &vcs_state_manager.get_commit_contents(&commit)?,
-            )?;
-            log_result.commit_list.last_mut().unwrap().1 = prev_file_changes;
-        }
-        log_result.commit_list.push((
-            vcs_state_manager.get_commit_data(&commit)?.unwrap(),
-            Vec::new(),
-        ));
-        prev_commit = Some(commit);
+    for commit in commits {
+        if options.max_count == Some(log_result.commit_list.len()) {
+            break;
+        }
+        let commit_data = vcs_state_manager
+            .get_commit_data(&commit)?
+            .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+        if !options.matches_metadata(&commit_data) {
+            continue;
+        }
+
+        let file_changes = if options.skip_changes && options.paths.is_empty() {
+            Vec::new()
+        } else {
+            get_commit_changes(&mut vcs_state_manager, &commit_data)?
+        };
+        let commit_dir = vcs_state_manager.get_commit_dir(&commit);
+        if !options.paths.is_empty() && !options.matches_paths(&commit_dir, &file_changes)? {
+            continue;
+        }
+        log_result.commit_list.push((commit_data, file_changes));
     }
 
     Ok(log_result)
 }
 
-pub fn log() -> Result<LogResult, std::io::Error> {
+/// Log commits, the paths in the options are relative to the current folder
+pub fn log(options: &LogOptions) -> Result<LogResult, VcsError> {
     let repo_dir = get_repo_dir()?;
-    log_in_repo(repo_dir)
+    let cur_dir = env::current_dir()?;
+    let mut options = options.clone();
+    for path in options.paths.iter_mut() {
+        let full_path = cur_dir.join(&path);
+        *path = normalize_relative_path(strip_prefix(&full_path, &repo_dir)?).ok_or_else(|| {
+            VcsError::PathNotFound {
+                revision: String::from("HEAD"),
+                path: path.clone(),
+            }
+        })?;
+    }
+    log_with_options_in_repo(repo_dir, &options)
 }
@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    repo_file_manager::{files_equal, get_file_changes, get_repo_dir, FileChange},
+    error::VcsError,
+    json_files::get_author,
+    repo_file_manager::{
+        copy_entry, files_equal, get_file_changes, get_repo_dir, strip_prefix, FileChange,
+    },
     vcs_state_manager::VcsStateManager,
 };
 
@@ -20,90 +27,170 @@ pub enum MergeResult {
 }
 
 /// Merge the given branch to master
-pub fn merge_in_repo(repo_dir: PathBuf, branch: &String) -> Result<MergeResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir);
+pub fn merge_in_repo(repo_dir: PathBuf, branch: &str) -> Result<MergeResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
 
-    if branch.clone() == String::from("master") {
+    if branch == "master" {
         return Ok(MergeResult::MergeWithMaster);
     }
 
-    let last_master_commit = vcs_state_manager
-        .get_commits(&String::from("master"))?
-        .unwrap()
-        .pop()
-        .unwrap();
-    let last_branch_commit = vcs_state_manager
-        .get_commits(&branch)?
-        .unwrap()
-        .pop()
-        .unwrap();
+    let last_master_commit = vcs_state_manager.get_last_commit("master")?;
+    let last_branch_commit = vcs_state_manager.get_last_commit(branch)?;
     if last_master_commit != vcs_state_manager.get_commit()? {
         return Ok(MergeResult::NotLastCommit);
     }
-    let common_commit = vcs_state_manager.get_commits(&branch)?.unwrap().remove(0);
+    let common_commit = vcs_state_manager.get_existing_commits(branch)?.remove(0);
 
     let master_contents = vcs_state_manager.get_commit_contents(&last_master_commit)?;
     let branch_contents = vcs_state_manager.get_commit_contents(&last_branch_commit)?;
     let common_contents = vcs_state_manager.get_commit_contents(&common_commit)?;
 
+    let hash_algorithm = vcs_state_manager.get_hash_algorithm()?;
     let master_dir = vcs_state_manager.get_commit_dir(&last_master_commit);
     let branch_dir = vcs_state_manager.get_commit_dir(&last_branch_commit);
     let common_dir = vcs_state_manager.get_commit_dir(&common_commit);
 
-    let file_changes_master =
-        get_file_changes(&master_dir, &master_contents, &common_dir, &common_contents)?;
-    let file_changes_branch =
-        get_file_changes(&branch_dir, &branch_contents, &common_dir, &common_contents)?;
+    let file_changes_master = get_file_changes(
+        hash_algorithm,
+        &master_dir,
+        &master_contents,
+        &common_dir,
+        &common_contents,
+    )?;
+    let file_changes_branch = get_file_changes(
+        hash_algorithm,
+        &branch_dir,
+        &branch_contents,
+        &common_dir,
+        &common_contents,
+    )?;
 
     let mut files_to_merge: Vec<PathBuf> = Vec::new();
     for entry in branch_contents.iter() {
         files_to_merge.push(entry.clone());
     }
+    // Old paths of files renamed on master, they are dropped from the branch files
+    let mut renamed_on_master: Vec<PathBuf> = Vec::new();
+    // Files which are merged from one side but placed at the path of the other side
+    let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
 
-    for entry in file_changes_master {
-        let same = file_changes_branch.iter().find(|x| {
-            x.1.strip_prefix(&branch_dir).unwrap() == entry.1.strip_prefix(&master_dir).unwrap()
-        });
-        if same.is_some() {
-            if !files_equal(&entry.1, &same.unwrap().1)? {
+    let branch_change = |relative_path: &Path| -> Result<Option<&(FileChange, PathBuf)>, VcsError> {
+        for branch_entry in file_changes_branch.iter() {
+            if strip_prefix(&branch_entry.1, &branch_dir)? == relative_path {
+                return Ok(Some(branch_entry));
+            }
+        }
+        Ok(None)
+    };
+
+    for entry in file_changes_master.iter() {
+        let relative_path = strip_prefix(&entry.1, &master_dir)?;
+
+        // A file renamed on master keeps the changes made on the branch to its old path
+        if let FileChange::Renamed { from, .. } = &entry.0 {
+            let from = strip_prefix(from, &master_dir)?;
+            renamed_on_master.push(branch_dir.join(from));
+            if let Some((FileChange::Modified | FileChange::ModeChanged { .. }, branch_path)) =
+                branch_change(from)?
+            {
+                if !files_equal(&entry.1, &common_dir.join(from))? {
+                    return Ok(MergeResult::MergeConflict {
+                        path1: entry.1.clone(),
+                        path2: branch_path.clone(),
+                    });
+                }
+                moved.push((branch_path.clone(), relative_path.to_path_buf()));
+                continue;
+            }
+        }
+
+        // A file renamed on the branch gets the changes made on master to its old path
+        let old_path = branch_dir.join(relative_path);
+        let renamed_on_branch = file_changes_branch.iter().find(
+            |(change, _)| matches!(change, FileChange::Renamed { from, .. } if *from == old_path),
+        );
+        if let (FileChange::Modified, Some((_, branch_path))) = (&entry.0, renamed_on_branch) {
+            if !files_equal(branch_path, &common_dir.join(relative_path))? {
                 return Ok(MergeResult::MergeConflict {
-                    path1: entry.1,
-                    path2: same.unwrap().1.clone(),
+                    path1: entry.1.clone(),
+                    path2: branch_path.clone(),
                 });
             }
-        } else {
-            if !files_to_merge
-                .contains(&(&branch_dir).join(entry.1.strip_prefix(&master_dir).unwrap()))
+            let branch_relative_path = strip_prefix(branch_path, &branch_dir)?.to_path_buf();
+            files_to_merge.retain(|x| x != branch_path);
+            moved.push((entry.1.clone(), branch_relative_path));
+            continue;
+        }
+
+        if entry.0 == FileChange::Removed {
+            if let Some((FileChange::Modified | FileChange::ModeChanged { .. }, branch_path)) =
+                branch_change(relative_path)?
             {
-                files_to_merge.push(entry.1);
+                return Ok(MergeResult::MergeConflict {
+                    path1: entry.1.clone(),
+                    path2: branch_path.clone(),
+                });
+            }
+            continue;
+        }
+
+        if let Some(same) = branch_change(relative_path)? {
+            if !files_equal(&entry.1, &same.1)? {
+                return Ok(MergeResult::MergeConflict {
+                    path1: entry.1.clone(),
+                    path2: same.1.clone(),
+                });
             }
+        } else if !files_to_merge.contains(&branch_dir.join(relative_path)) {
+            files_to_merge.push(entry.1.clone());
         }
     }
 
-    let old_commit = vcs_state_manager.get_commit()?;
-    vcs_state_manager.commit_contents(
-        &String::from(format!("Merged branch {}", branch)),
-        &String::from("master"),
+    files_to_merge.retain(|x| !renamed_on_master.contains(x));
+
+    // Moved files are put into a staging folder, which mirrors paths of the merged commit
+    let staging_dir = repo_dir.join(".vcs").join("staging").join("merge");
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)?;
+    }
+    for (source, relative_path) in moved.iter() {
+        let staged_path = staging_dir.join(relative_path);
+        if let Some(parent) = staged_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        copy_entry(source, &staged_path)?;
+        files_to_merge.push(staged_path);
+    }
+
+    let message = format!("Merged branch {}", branch);
+    let new_commit = vcs_state_manager.create_commit(
+        &message,
+        &get_author(),
         &files_to_merge,
+        vec![last_master_commit, last_branch_commit],
     )?;
-    let new_commit = vcs_state_manager.get_commit()?;
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)?;
+    }
+    vcs_state_manager.add_branch_commit("master", &new_commit, &format!("merge: {}", message))?;
+    vcs_state_manager.set_branch("master")?;
     let file_changes = get_file_changes(
+        hash_algorithm,
         &vcs_state_manager.get_commit_dir(&new_commit),
         &vcs_state_manager.get_commit_contents(&new_commit)?,
         &master_dir,
         &master_contents,
     )?;
 
-    vcs_state_manager.remove_branch(branch)?;
-    vcs_state_manager.set_commit(&old_commit).unwrap();
+    vcs_state_manager.remove_branch(branch, "merge: merged into master")?;
 
-    return Ok(MergeResult::Success {
+    Ok(MergeResult::Success {
         commit: new_commit,
         file_changes,
-    });
+    })
 }
 
-pub fn merge(branch: &String) -> Result<MergeResult, std::io::Error> {
+pub fn merge(branch: &str) -> Result<MergeResult, VcsError> {
     let repo_dir = get_repo_dir()?;
     merge_in_repo(repo_dir, branch)
 }
@@ -1,7 +1,21 @@
+pub mod bisect;
+pub mod blame;
+pub mod cherry_pick;
 pub mod commit;
+pub mod fsck;
+pub mod grep;
 pub mod init;
 pub mod jump;
+pub mod lfs;
 pub mod log;
 pub mod merge;
 pub mod new_branch;
+pub mod rebase;
+pub mod reflog;
+pub mod reset;
+pub mod restore;
+pub mod revert;
+pub mod show;
+pub mod stash;
 pub mod status;
+pub mod upgrade;
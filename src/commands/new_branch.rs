@@ -1,37 +1,63 @@
 use std::path::PathBuf;
 
-use crate::{repo_file_manager::get_repo_dir, vcs_state_manager::VcsStateManager};
+use crate::{
+    error::VcsError, json_files::check_branch_name, repo_file_manager::get_repo_dir,
+    vcs_state_manager::VcsStateManager,
+};
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum NewBranchResult {
     OnlyFromMaster,
     Success { commit: String },
     AlreadyExists,
+    Restored { commit: String },
+    NotFound,
 }
 
 /// Create a new branch from the current commit
 pub fn new_branch_in_repo(
     repo_dir: PathBuf,
-    new_branch: &String,
-) -> Result<NewBranchResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir);
+    new_branch: &str,
+) -> Result<NewBranchResult, VcsError> {
+    check_branch_name(new_branch)?;
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
 
     let cur_branch = vcs_state_manager.get_branch()?;
-    if cur_branch != String::from("master") {
+    if cur_branch != "master" {
         Ok(NewBranchResult::OnlyFromMaster)
+    } else if new_branch != cur_branch {
+        vcs_state_manager.new_branch(new_branch)?;
+        Ok(NewBranchResult::Success {
+            commit: vcs_state_manager.get_commit()?,
+        })
     } else {
-        if *new_branch != cur_branch {
-            vcs_state_manager.new_branch(new_branch)?;
-            Ok(NewBranchResult::Success {
-                commit: vcs_state_manager.get_commit()?,
-            })
-        } else {
-            Ok(NewBranchResult::AlreadyExists)
-        }
+        Ok(NewBranchResult::AlreadyExists)
     }
 }
 
-pub fn new_branch(new_branch: &String) -> Result<NewBranchResult, std::io::Error> {
+/// Restore a deleted branch from its reflog
+pub fn restore_branch_in_repo(
+    repo_dir: PathBuf,
+    branch: &str,
+) -> Result<NewBranchResult, VcsError> {
+    check_branch_name(branch)?;
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    if vcs_state_manager.get_commits(branch)?.is_some() {
+        return Ok(NewBranchResult::AlreadyExists);
+    }
+    match vcs_state_manager.restore_branch(branch)? {
+        Some(commit) => Ok(NewBranchResult::Restored { commit }),
+        None => Ok(NewBranchResult::NotFound),
+    }
+}
+
+pub fn new_branch(new_branch: &str) -> Result<NewBranchResult, VcsError> {
     let repo_dir = get_repo_dir()?;
     new_branch_in_repo(repo_dir, new_branch)
 }
+
+pub fn restore_branch(branch: &str) -> Result<NewBranchResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    restore_branch_in_repo(repo_dir, branch)
+}
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::cherry_pick::{abort_sequencer, replay_commits, ReplayResult},
+    error::VcsError,
+    json_files::SequencerData,
+    repo_file_manager::{get_repo_dir, FileChange},
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum RebaseResult {
+    UncommitedChanges {
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    NotLastCommit,
+    /// Another cherry-pick or rebase has to be continued or aborted first
+    InProgress {
+        operation: String,
+    },
+    /// There is no rebase to continue or abort
+    NotInProgress,
+    /// The working tree contains the conflicting files, resolve them and continue
+    MergeConflict {
+        commit: String,
+        paths: Vec<PathBuf>,
+    },
+    Success {
+        branch: String,
+        commit: String,
+        commits: Vec<String>,
+    },
+    Aborted {
+        commit: String,
+    },
+}
+
+/// Convert the outcome of replaying commits, the branch tip is the current commit
+fn get_rebase_result(
+    vcs_state_manager: &mut VcsStateManager,
+    branch: String,
+    replay_result: ReplayResult,
+) -> Result<RebaseResult, VcsError> {
+    match replay_result {
+        ReplayResult::Conflict { commit, paths } => {
+            Ok(RebaseResult::MergeConflict { commit, paths })
+        }
+        ReplayResult::Done { commits } => Ok(RebaseResult::Success {
+            branch,
+            commit: vcs_state_manager.get_commit()?,
+            commits,
+        }),
+    }
+}
+
+/// Replay the commits of the current branch which are not in upstream on top of `onto`
+///
+/// Without `onto` the commits are replayed on top of upstream.
+pub fn rebase_in_repo(
+    repo_dir: PathBuf,
+    upstream: &str,
+    onto: Option<&str>,
+) -> Result<RebaseResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    if let Some(sequencer) = vcs_state_manager.get_sequencer()? {
+        return Ok(RebaseResult::InProgress {
+            operation: sequencer.operation,
+        });
+    }
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let branch = vcs_state_manager.get_branch()?;
+    let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
+    if !file_changes.is_empty() {
+        return Ok(RebaseResult::UncommitedChanges { file_changes });
+    }
+    if vcs_state_manager.get_last_commit(&branch)? != cur_commit {
+        return Ok(RebaseResult::NotLastCommit);
+    }
+
+    let upstream_commit = vcs_state_manager.resolve_revision(upstream)?;
+    let new_base = match onto {
+        Some(onto) => vcs_state_manager.resolve_revision(onto)?,
+        None => upstream_commit.clone(),
+    };
+    let upstream_ancestors = vcs_state_manager.get_ancestors(&upstream_commit)?;
+
+    let mut todo: Vec<String> = Vec::new();
+    let mut commit = Some(cur_commit.clone());
+    while let Some(cur) = commit {
+        if upstream_ancestors.contains(&cur) {
+            break;
+        }
+        commit = vcs_state_manager.get_parent(&cur, 0)?;
+        todo.push(cur);
+    }
+    todo.reverse();
+
+    let message = format!("rebase: checkout {}", onto.unwrap_or(upstream));
+    vcs_state_manager.set_branch_tip(&branch, &new_base, &message)?;
+    vcs_state_manager.jump_to_commit(&new_base, Some(&branch))?;
+
+    let sequencer = SequencerData {
+        operation: String::from("rebase"),
+        branch: branch.clone(),
+        orig_commit: cur_commit,
+        todo,
+    };
+    let replay_result = replay_commits(&mut vcs_state_manager, &repo_dir, sequencer, false)?;
+    get_rebase_result(&mut vcs_state_manager, branch, replay_result)
+}
+
+/// Commit the resolved conflict and replay the remaining commits
+pub fn rebase_continue_in_repo(repo_dir: PathBuf) -> Result<RebaseResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    match vcs_state_manager.get_sequencer()? {
+        Some(sequencer) if sequencer.operation == "rebase" => {
+            let branch = sequencer.branch.clone();
+            let replay_result = replay_commits(&mut vcs_state_manager, &repo_dir, sequencer, true)?;
+            get_rebase_result(&mut vcs_state_manager, branch, replay_result)
+        }
+        Some(sequencer) => Ok(RebaseResult::InProgress {
+            operation: sequencer.operation,
+        }),
+        None => Ok(RebaseResult::NotInProgress),
+    }
+}
+
+/// Return the branch to the commit it has pointed to before the rebase
+pub fn rebase_abort_in_repo(repo_dir: PathBuf) -> Result<RebaseResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    match vcs_state_manager.get_sequencer()? {
+        Some(sequencer) if sequencer.operation == "rebase" => {
+            abort_sequencer(&mut vcs_state_manager, &sequencer)?;
+            Ok(RebaseResult::Aborted {
+                commit: sequencer.orig_commit,
+            })
+        }
+        Some(sequencer) => Ok(RebaseResult::InProgress {
+            operation: sequencer.operation,
+        }),
+        None => Ok(RebaseResult::NotInProgress),
+    }
+}
+
+pub fn rebase(upstream: &str, onto: Option<&str>) -> Result<RebaseResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    rebase_in_repo(repo_dir, upstream, onto)
+}
+
+pub fn rebase_continue() -> Result<RebaseResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    rebase_continue_in_repo(repo_dir)
+}
+
+pub fn rebase_abort() -> Result<RebaseResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    rebase_abort_in_repo(repo_dir)
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use crate::{
+    error::VcsError, json_files::ReflogEntry, repo_file_manager::get_repo_dir,
+    vcs_state_manager::VcsStateManager,
+};
+
+pub struct ReflogResult {
+    /// Reflog entries, the newest ones go first
+    pub entries: Vec<ReflogEntry>,
+}
+
+/// Show movements of HEAD or of the given branch
+pub fn reflog_in_repo(repo_dir: PathBuf, branch: Option<&str>) -> Result<ReflogResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let mut entries = vcs_state_manager.get_reflog(branch)?;
+    if let Some(branch) = branch {
+        if entries.is_empty() && vcs_state_manager.get_commits(branch)?.is_none() {
+            return Err(VcsError::BranchNotFound(branch.to_string()));
+        }
+    }
+    entries.reverse();
+    Ok(ReflogResult { entries })
+}
+
+pub fn reflog(branch: Option<&str>) -> Result<ReflogResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    reflog_in_repo(repo_dir, branch)
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use crate::{
+    error::VcsError,
+    repo_file_manager::{copy_files_from_commit, get_repo_dir, remove_repo_files, FileChange},
+    vcs_state_manager::VcsStateManager,
+};
+
+/// How much of the repository is moved to the new commit
+///
+/// There is no staging area, so `Soft` and `Mixed` both keep the working tree.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ResetMode {
+    Soft,
+    Mixed,
+    Hard,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct ResetResult {
+    pub branch: String,
+    pub old_commit: String,
+    pub commit: String,
+    /// Changes left in the working tree relative to the new commit
+    pub file_changes: Vec<(FileChange, PathBuf)>,
+}
+
+/// Move the tip of the current branch and HEAD to the given revision
+pub fn reset_in_repo(
+    repo_dir: PathBuf,
+    revision: &str,
+    mode: ResetMode,
+) -> Result<ResetResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let commit = vcs_state_manager.resolve_revision(revision)?;
+    let branch = vcs_state_manager.get_branch()?;
+    let old_commit = vcs_state_manager.get_commit()?;
+
+    let message = format!("reset: moving to {}", revision);
+    vcs_state_manager.set_branch_tip(&branch, &commit, &message)?;
+    vcs_state_manager.set_commit(&commit, &message)?;
+    if mode == ResetMode::Hard {
+        remove_repo_files(&repo_dir)?;
+        copy_files_from_commit(&repo_dir, &commit)?;
+    }
+
+    Ok(ResetResult {
+        branch,
+        old_commit,
+        file_changes: vcs_state_manager.get_file_changes_commit(&commit)?,
+        commit,
+    })
+}
+
+pub fn reset(revision: &str, mode: ResetMode) -> Result<ResetResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    reset_in_repo(repo_dir, revision, mode)
+}
@@ -0,0 +1,128 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::VcsError,
+    large_objects::LargeObjects,
+    repo_file_manager::{
+        copy_files_from_commit, get_contents, get_entry_kind, get_file_changes, get_repo_dir,
+        normalize_relative_path, remove_repo_files, strip_prefix, EntryKind, FileChange,
+    },
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct RestoreResult {
+    pub commit: String,
+    /// Changes made to the working tree, an empty list means the paths were already up to date
+    pub file_changes: Vec<(FileChange, PathBuf)>,
+}
+
+/// Check if the path exists without following symlinks
+fn entry_exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+/// Get all folders and files from the path if it exists
+fn get_contents_if_exists(path: &Path) -> Result<Vec<PathBuf>, VcsError> {
+    if entry_exists(path) {
+        get_contents(path, true)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// Replace the path in the repository with its version from the commit folder
+fn restore_path(repo_dir: &Path, commit_dir: &Path, relative_path: &Path) -> Result<(), VcsError> {
+    let repo_path = repo_dir.join(relative_path);
+    if entry_exists(&repo_path) {
+        if get_entry_kind(&repo_path)? == EntryKind::Dir {
+            fs::remove_dir_all(&repo_path)?;
+        } else {
+            fs::remove_file(&repo_path)?;
+        }
+    }
+    if let Some(parent) = repo_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let large_objects = LargeObjects::open(repo_dir)?;
+    for entry in get_contents(&commit_dir.join(relative_path), false)? {
+        let new_path = repo_dir.join(strip_prefix(&entry, commit_dir)?);
+        large_objects.checkout(&entry, &new_path)?;
+    }
+    Ok(())
+}
+
+/// Restore the given paths relative to the repository from the source revision
+pub fn restore_in_repo(
+    repo_dir: PathBuf,
+    source: &str,
+    paths: &[PathBuf],
+) -> Result<RestoreResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let commit = vcs_state_manager.resolve_revision(source)?;
+    let commit_dir = vcs_state_manager.get_commit_dir(&commit);
+
+    let mut relative_paths: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        let not_found = || VcsError::PathNotFound {
+            revision: source.to_string(),
+            path: path.clone(),
+        };
+        let relative_path = normalize_relative_path(path).ok_or_else(not_found)?;
+        if relative_path.starts_with(".vcs") || !entry_exists(&commit_dir.join(&relative_path)) {
+            return Err(not_found());
+        }
+        relative_paths.push(relative_path);
+    }
+
+    let mut file_changes: Vec<(FileChange, PathBuf)> = Vec::new();
+    for relative_path in relative_paths {
+        let commit_contents = get_contents(&commit_dir.join(&relative_path), false)?;
+        let repo_contents = get_contents_if_exists(&repo_dir.join(&relative_path))?;
+        let changes = get_file_changes(
+            vcs_state_manager.get_hash_algorithm()?,
+            &commit_dir,
+            &commit_contents,
+            &repo_dir,
+            &repo_contents,
+        )?;
+        if changes.is_empty() {
+            continue;
+        }
+
+        if relative_path.as_os_str().is_empty() {
+            remove_repo_files(&repo_dir)?;
+            copy_files_from_commit(&repo_dir, &commit)?;
+        } else {
+            restore_path(&repo_dir, &commit_dir, &relative_path)?;
+        }
+        for (change, path) in changes {
+            let path = repo_dir.join(strip_prefix(&path, &commit_dir)?);
+            if !file_changes.iter().any(|x| x.1 == path) {
+                file_changes.push((change, path));
+            }
+        }
+    }
+    file_changes.sort_by(|x, y| x.1.cmp(&y.1));
+
+    Ok(RestoreResult {
+        commit,
+        file_changes,
+    })
+}
+
+/// Restore paths relative to the current folder
+pub fn restore(source: &str, paths: &[PathBuf]) -> Result<RestoreResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    let cur_dir = env::current_dir()?;
+    let mut relative_paths: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        relative_paths.push(strip_prefix(&cur_dir.join(path), &repo_dir)?.to_path_buf());
+    }
+    restore_in_repo(repo_dir, source, &relative_paths)
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use crate::{
+    error::VcsError,
+    repo_file_manager::{get_repo_dir, FileChange},
+    tree_merge::merge_trees,
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum RevertResult {
+    UncommitedChanges {
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    NotLastCommit,
+    /// The changes of the commit are already undone in the current commit
+    NothingToRevert,
+    /// The working tree contains the partially reverted files, commit them after resolving the conflicts
+    MergeConflict {
+        message: String,
+        paths: Vec<PathBuf>,
+    },
+    Success {
+        commit: String,
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+}
+
+/// Create a commit undoing the changes the given revision made to its first parent
+pub fn revert_in_repo(repo_dir: PathBuf, revision: &str) -> Result<RevertResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let branch = vcs_state_manager.get_branch()?;
+    let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
+    if !file_changes.is_empty() {
+        return Ok(RevertResult::UncommitedChanges { file_changes });
+    }
+    if vcs_state_manager.get_last_commit(&branch)? != cur_commit {
+        return Ok(RevertResult::NotLastCommit);
+    }
+
+    let commit = vcs_state_manager.resolve_revision(revision)?;
+    let commit_data = vcs_state_manager
+        .get_commit_data(&commit)?
+        .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+    let parent_dir = commit_data
+        .parents
+        .first()
+        .map(|parent| vcs_state_manager.get_commit_dir(parent));
+    let message = format!(
+        "Revert \"{}\"\n\nThis reverts commit {}.",
+        commit_data.message, commit
+    );
+
+    let paths = merge_trees(
+        &repo_dir,
+        Some(&vcs_state_manager.get_commit_dir(&commit)),
+        parent_dir.as_deref(),
+        "HEAD",
+        &format!("parent of {}", commit),
+    )?;
+    if !paths.is_empty() {
+        return Ok(RevertResult::MergeConflict { message, paths });
+    }
+
+    let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
+    if file_changes.is_empty() {
+        return Ok(RevertResult::NothingToRevert);
+    }
+    let new_commit = vcs_state_manager.commit(&message, &branch)?;
+    Ok(RevertResult::Success {
+        commit: new_commit,
+        file_changes,
+    })
+}
+
+pub fn revert(revision: &str) -> Result<RevertResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    revert_in_repo(repo_dir, revision)
+}
@@ -0,0 +1,140 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    diff::{is_binary, split_lines, unified_diff},
+    error::VcsError,
+    json_files::CommitData,
+    repo_file_manager::{
+        get_file_changes, get_repo_dir, normalize_relative_path, read_entry, strip_prefix,
+        FileChange,
+    },
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct FileDiff {
+    pub change: FileChange,
+    /// Path relative to the repository
+    pub path: PathBuf,
+    pub binary: bool,
+    /// Unified diff hunks, empty for folders and binary files
+    pub lines: Vec<String>,
+}
+
+pub enum ShowResult {
+    Commit {
+        commit_data: CommitData,
+        file_diffs: Vec<FileDiff>,
+    },
+    File {
+        contents: Vec<u8>,
+    },
+    Directory {
+        entries: Vec<PathBuf>,
+    },
+}
+
+/// Read a file or a symlink target if it exists, folders and missing files are empty
+fn read_if_file(path: &PathBuf) -> Result<Vec<u8>, VcsError> {
+    if fs::symlink_metadata(path).is_err() {
+        return Ok(Vec::new());
+    }
+    Ok(read_entry(path)?.unwrap_or_default())
+}
+
+/// Get the difference between the commit and its first parent
+fn get_commit_diff(
+    vcs_state_manager: &mut VcsStateManager,
+    commit_data: &CommitData,
+) -> Result<Vec<FileDiff>, VcsError> {
+    let commit_dir = vcs_state_manager.get_commit_dir(&commit_data.hash);
+    let commit_contents = vcs_state_manager.get_commit_contents(&commit_data.hash)?;
+    let (parent_dir, parent_contents) = match commit_data.parents.first() {
+        Some(parent) => (
+            vcs_state_manager.get_commit_dir(parent),
+            vcs_state_manager.get_commit_contents(parent)?,
+        ),
+        None => (commit_dir.clone(), Vec::new()),
+    };
+
+    let mut file_diffs: Vec<FileDiff> = Vec::new();
+    for (change, path) in get_file_changes(
+        vcs_state_manager.get_hash_algorithm()?,
+        &commit_dir,
+        &commit_contents,
+        &parent_dir,
+        &parent_contents,
+    )? {
+        let relative_path = strip_prefix(&path, &commit_dir)?.to_path_buf();
+        let change = change.relative_to(&commit_dir)?;
+        let old_path = match &change {
+            FileChange::Renamed { from, .. } | FileChange::Copied { from, .. } => from,
+            _ => &relative_path,
+        };
+        let old_data = read_if_file(&parent_dir.join(old_path))?;
+        let new_data = read_if_file(&path)?;
+        let binary = is_binary(&old_data) || is_binary(&new_data);
+        let lines = if binary {
+            Vec::new()
+        } else {
+            unified_diff(&split_lines(&old_data), &split_lines(&new_data), 3)
+        };
+        file_diffs.push(FileDiff {
+            change,
+            path: relative_path,
+            binary,
+            lines,
+        });
+    }
+    file_diffs.sort_by(|x, y| x.path.cmp(&y.path));
+    Ok(file_diffs)
+}
+
+/// Show a commit with its changes or the contents of `<revision>:<path>`
+pub fn show_in_repo(repo_dir: PathBuf, revision: &str) -> Result<ShowResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    match revision.split_once(':') {
+        Some((revision, path)) => {
+            let commit = vcs_state_manager.resolve_revision(revision)?;
+            let not_found = || VcsError::PathNotFound {
+                revision: revision.to_string(),
+                path: PathBuf::from(path),
+            };
+            let relative_path =
+                normalize_relative_path(&PathBuf::from(path)).ok_or_else(not_found)?;
+            let commit_dir = vcs_state_manager.get_commit_dir(&commit);
+            let full_path = commit_dir.join(relative_path);
+
+            if fs::symlink_metadata(&full_path).is_err() {
+                Err(not_found())
+            } else if let Some(contents) = read_entry(&full_path)? {
+                Ok(ShowResult::File { contents })
+            } else {
+                let mut entries: Vec<PathBuf> = Vec::new();
+                for entry in fs::read_dir(&full_path)? {
+                    entries.push(strip_prefix(&entry?.path(), &commit_dir)?.to_path_buf());
+                }
+                entries.sort();
+                Ok(ShowResult::Directory { entries })
+            }
+        }
+
+        None => {
+            let commit = vcs_state_manager.resolve_revision(revision)?;
+            let commit_data = vcs_state_manager
+                .get_commit_data(&commit)?
+                .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+            let file_diffs = get_commit_diff(&mut vcs_state_manager, &commit_data)?;
+            Ok(ShowResult::Commit {
+                commit_data,
+                file_diffs,
+            })
+        }
+    }
+}
+
+pub fn show(revision: &str) -> Result<ShowResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    show_in_repo(repo_dir, revision)
+}
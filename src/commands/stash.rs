@@ -0,0 +1,216 @@
+use std::path::PathBuf;
+
+use crate::{
+    error::VcsError,
+    json_files::{get_author, CommitData, StashData},
+    repo_file_manager::{
+        copy_files_from_commit, get_contents, get_file_changes, get_repo_dir, remove_repo_files,
+        strip_prefix, FileChange,
+    },
+    tree_merge::merge_trees,
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum StashResult {
+    NoLocalChanges,
+    UncommitedChanges {
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    NotFound,
+    Pushed {
+        commit: String,
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    List {
+        stashes: Vec<(StashData, CommitData)>,
+    },
+    /// Changes saved in the stash with paths relative to the repository
+    Show {
+        file_changes: Vec<(FileChange, PathBuf)>,
+    },
+    /// The working tree contains the conflicting files, the stash is kept
+    MergeConflict {
+        paths: Vec<PathBuf>,
+    },
+    Applied {
+        commit: String,
+        dropped: bool,
+    },
+    Dropped {
+        commit: String,
+    },
+}
+
+/// Save local changes as a stash and reset the working tree to the current commit
+pub fn stash_push_in_repo(
+    repo_dir: PathBuf,
+    message: Option<&str>,
+) -> Result<StashResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let branch = vcs_state_manager.get_branch()?;
+    let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
+    if file_changes.is_empty() {
+        return Ok(StashResult::NoLocalChanges);
+    }
+
+    let message = match message {
+        Some(message) => format!("On {}: {}", branch, message),
+        None => format!("WIP on {}: {}", branch, cur_commit),
+    };
+    let contents = get_contents(&repo_dir, true)?;
+    let commit = vcs_state_manager.create_commit(
+        &message,
+        &get_author(),
+        &contents,
+        vec![cur_commit.clone()],
+    )?;
+
+    let mut stash_list = vcs_state_manager.get_stash_list()?;
+    stash_list.stashes.insert(
+        0,
+        StashData {
+            commit: commit.clone(),
+            branch,
+        },
+    );
+    vcs_state_manager.set_stash_list(stash_list)?;
+
+    remove_repo_files(&repo_dir)?;
+    copy_files_from_commit(&repo_dir, &cur_commit)?;
+    Ok(StashResult::Pushed {
+        commit,
+        file_changes,
+    })
+}
+
+/// Get all stashes with the newest one first
+pub fn stash_list_in_repo(repo_dir: PathBuf) -> Result<StashResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let mut stashes: Vec<(StashData, CommitData)> = Vec::new();
+    for stash_data in vcs_state_manager.get_stash_list()?.stashes {
+        let commit_data = vcs_state_manager
+            .get_commit_data(&stash_data.commit)?
+            .ok_or_else(|| VcsError::UnknownRevision(stash_data.commit.clone()))?;
+        stashes.push((stash_data, commit_data));
+    }
+    Ok(StashResult::List { stashes })
+}
+
+/// Get the changes saved in the stash
+pub fn stash_show_in_repo(repo_dir: PathBuf, index: usize) -> Result<StashResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let stash_list = vcs_state_manager.get_stash_list()?;
+    let stash_data = match stash_list.stashes.get(index) {
+        Some(stash_data) => stash_data,
+        None => return Ok(StashResult::NotFound),
+    };
+    let parent = vcs_state_manager
+        .get_parent(&stash_data.commit, 0)?
+        .ok_or_else(|| VcsError::UnknownRevision(stash_data.commit.clone()))?;
+
+    let stash_dir = vcs_state_manager.get_commit_dir(&stash_data.commit);
+    let mut file_changes: Vec<(FileChange, PathBuf)> = Vec::new();
+    for (change, path) in get_file_changes(
+        vcs_state_manager.get_hash_algorithm()?,
+        &stash_dir,
+        &vcs_state_manager.get_commit_contents(&stash_data.commit)?,
+        &vcs_state_manager.get_commit_dir(&parent),
+        &vcs_state_manager.get_commit_contents(&parent)?,
+    )? {
+        file_changes.push((
+            change.relative_to(&stash_dir)?,
+            strip_prefix(&path, &stash_dir)?.to_path_buf(),
+        ));
+    }
+    file_changes.sort_by(|x, y| x.1.cmp(&y.1));
+    Ok(StashResult::Show { file_changes })
+}
+
+/// Merge the stashed changes into the working tree, pop also drops the stash
+pub fn stash_apply_in_repo(
+    repo_dir: PathBuf,
+    index: usize,
+    pop: bool,
+) -> Result<StashResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir.clone())?;
+
+    let cur_commit = vcs_state_manager.get_commit()?;
+    let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
+    if !file_changes.is_empty() {
+        return Ok(StashResult::UncommitedChanges { file_changes });
+    }
+
+    let mut stash_list = vcs_state_manager.get_stash_list()?;
+    let commit = match stash_list.stashes.get(index) {
+        Some(stash_data) => stash_data.commit.clone(),
+        None => return Ok(StashResult::NotFound),
+    };
+    let parent_dir = vcs_state_manager
+        .get_parent(&commit, 0)?
+        .map(|parent| vcs_state_manager.get_commit_dir(&parent));
+
+    let paths = merge_trees(
+        &repo_dir,
+        parent_dir.as_deref(),
+        Some(&vcs_state_manager.get_commit_dir(&commit)),
+        "Updated upstream",
+        "Stashed changes",
+    )?;
+    if !paths.is_empty() {
+        return Ok(StashResult::MergeConflict { paths });
+    }
+
+    if pop {
+        stash_list.stashes.remove(index);
+        vcs_state_manager.set_stash_list(stash_list)?;
+    }
+    Ok(StashResult::Applied {
+        commit,
+        dropped: pop,
+    })
+}
+
+/// Remove the stash from the stash list
+pub fn stash_drop_in_repo(repo_dir: PathBuf, index: usize) -> Result<StashResult, VcsError> {
+    let vcs_state_manager = VcsStateManager::open(repo_dir)?;
+
+    let mut stash_list = vcs_state_manager.get_stash_list()?;
+    if index >= stash_list.stashes.len() {
+        return Ok(StashResult::NotFound);
+    }
+    let stash_data = stash_list.stashes.remove(index);
+    vcs_state_manager.set_stash_list(stash_list)?;
+    Ok(StashResult::Dropped {
+        commit: stash_data.commit,
+    })
+}
+
+pub fn stash_push(message: Option<&str>) -> Result<StashResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    stash_push_in_repo(repo_dir, message)
+}
+
+pub fn stash_list() -> Result<StashResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    stash_list_in_repo(repo_dir)
+}
+
+pub fn stash_show(index: usize) -> Result<StashResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    stash_show_in_repo(repo_dir, index)
+}
+
+pub fn stash_apply(index: usize, pop: bool) -> Result<StashResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    stash_apply_in_repo(repo_dir, index, pop)
+}
+
+pub fn stash_drop(index: usize) -> Result<StashResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    stash_drop_in_repo(repo_dir, index)
+}
@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use crate::{
+    error::VcsError,
     repo_file_manager::{get_repo_dir, FileChange},
     vcs_state_manager::VcsStateManager,
 };
@@ -12,8 +13,8 @@ pub struct StatusResult {
 }
 
 /// Print changes to be committed
-pub fn status_in_repo(repo_dir: PathBuf) -> Result<StatusResult, std::io::Error> {
-    let mut vcs_state_manager = VcsStateManager::init(repo_dir);
+pub fn status_in_repo(repo_dir: PathBuf) -> Result<StatusResult, VcsError> {
+    let mut vcs_state_manager = VcsStateManager::open(repo_dir)?;
 
     let cur_commit = vcs_state_manager.get_commit()?;
     let file_changes = vcs_state_manager.get_file_changes_commit(&cur_commit)?;
@@ -23,7 +24,7 @@ pub fn status_in_repo(repo_dir: PathBuf) -> Result<StatusResult, std::io::Error>
     })
 }
 
-pub fn status() -> Result<StatusResult, std::io::Error> {
+pub fn status() -> Result<StatusResult, VcsError> {
     let repo_dir = get_repo_dir()?;
     status_in_repo(repo_dir)
 }
@@ -0,0 +1,257 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, iter,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::VcsError,
+    hash::HashAlgorithm,
+    json_files::{
+        get_commit, get_format, get_reflog, get_reflog_branches, set_commit, set_format,
+        set_reflog, CommitData, FormatData, FORMAT_VERSION,
+    },
+    repo_file_manager::{
+        copy_entry, get_commit_hash, get_contents, get_contents_hash, get_entry_kind, get_repo_dir,
+        set_executable, strip_prefix, EntryKind,
+    },
+    vcs_state_manager::VcsStateManager,
+};
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum UpgradeResult {
+    UpToDate { version: u32 },
+    Upgraded { from: u32, to: u32, backup: PathBuf },
+}
+
+/// Copy everything in .vcs except for older backups to .vcs/backups
+fn backup_vcs_dir(repo_dir: &Path, version: u32) -> Result<PathBuf, VcsError> {
+    let vcs_dir = repo_dir.join(".vcs");
+    let backups_dir = vcs_dir.join("backups");
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_millis())
+        .unwrap_or(0);
+    let backup_dir = backups_dir.join(format!("v{}-{}", version, millis));
+
+    fs::create_dir_all(&backup_dir)?;
+    for entry in get_contents(&vcs_dir, false)?[1..].iter() {
+        if entry.starts_with(&backups_dir) {
+            continue;
+        }
+        let new_path = backup_dir.join(strip_prefix(entry, &vcs_dir)?);
+        copy_entry(entry, &new_path)?;
+    }
+    Ok(backup_dir)
+}
+
+/// Compute the snapshot hashes and commit ids again and replace the old ids everywhere
+///
+/// Commit ids are derived from snapshot hashes, so they change whenever the way
+/// snapshots are hashed changes. Parents get their new ids before their children.
+/// Commit messages keep the ids they mention, backups keep the old ids.
+fn rehash_commits(repo_dir: &Path) -> Result<(), VcsError> {
+    let mut vcs_state_manager = VcsStateManager::init(repo_dir.to_path_buf());
+    let hash_algorithm = get_format(repo_dir)?.hash;
+
+    let mut commit_list = vcs_state_manager.get_commit_list()?;
+    for commit_data in commit_list.commits.iter_mut() {
+        let contents = vcs_state_manager.get_commit_contents(&commit_data.hash)?;
+        commit_data.tree = get_contents_hash(repo_dir, &contents)?;
+    }
+
+    let listed: HashSet<String> = commit_list.commits.iter().map(|x| x.hash.clone()).collect();
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut left: Vec<&mut CommitData> = commit_list.commits.iter_mut().collect();
+    while !left.is_empty() {
+        let left_count = left.len();
+        left.retain_mut(|commit_data| {
+            // Parents which are not listed keep their ids
+            if commit_data
+                .parents
+                .iter()
+                .any(|x| listed.contains(x) && !ids.contains_key(x))
+            {
+                return true;
+            }
+            for parent in commit_data.parents.iter_mut() {
+                if let Some(id) = ids.get(parent) {
+                    *parent = id.clone();
+                }
+            }
+            let id = get_commit_hash(
+                hash_algorithm,
+                &commit_data.tree,
+                &commit_data.parents,
+                &commit_data.message,
+                &commit_data.author,
+                commit_data.date,
+            );
+            ids.insert(commit_data.hash.clone(), id.clone());
+            commit_data.hash = id;
+            false
+        });
+        if left.len() == left_count {
+            return Err(VcsError::CorruptMetadata {
+                file: repo_dir.join(".vcs").join("commit_list.json"),
+            });
+        }
+    }
+    let new_id = |commit: &mut String| {
+        if let Some(id) = ids.get(commit) {
+            *commit = id.clone();
+        }
+    };
+
+    let commits_dir = repo_dir.join(".vcs").join("commits");
+    for (old, new) in ids.iter() {
+        if old != new && commits_dir.join(old).is_dir() {
+            fs::rename(commits_dir.join(old), commits_dir.join(new))?;
+        }
+    }
+    vcs_state_manager.set_commit_list(commit_list)?;
+
+    let mut branch_list = vcs_state_manager.get_branch_list()?;
+    for branch_data in branch_list.branches.iter_mut() {
+        branch_data.commits.iter_mut().for_each(new_id);
+    }
+    vcs_state_manager.set_branch_list(branch_list)?;
+
+    let mut commit = get_commit(repo_dir)?;
+    new_id(&mut commit);
+    set_commit(repo_dir, &commit)?;
+
+    let mut stash_list = vcs_state_manager.get_stash_list()?;
+    for stash_data in stash_list.stashes.iter_mut() {
+        new_id(&mut stash_data.commit);
+    }
+    vcs_state_manager.set_stash_list(stash_list)?;
+
+    if let Some(mut sequencer) = vcs_state_manager.get_sequencer()? {
+        new_id(&mut sequencer.orig_commit);
+        sequencer.todo.iter_mut().for_each(new_id);
+        vcs_state_manager.set_sequencer(Some(&sequencer))?;
+    }
+    if let Some(mut bisect) = vcs_state_manager.get_bisect()? {
+        new_id(&mut bisect.orig_commit);
+        bisect.bad.iter_mut().for_each(new_id);
+        bisect.good.iter_mut().for_each(new_id);
+        bisect.skipped.iter_mut().for_each(new_id);
+        vcs_state_manager.set_bisect(Some(&bisect))?;
+    }
+
+    let branches = get_reflog_branches(repo_dir)?;
+    let reflogs = iter::once(None).chain(branches.iter().map(|x| Some(x.as_str())));
+    for branch in reflogs {
+        let mut entries = get_reflog(repo_dir, branch)?;
+        if entries.is_empty() {
+            continue;
+        }
+        for entry in entries.iter_mut() {
+            new_id(&mut entry.old);
+            new_id(&mut entry.new);
+        }
+        set_reflog(repo_dir, branch, &entries)?;
+    }
+    Ok(())
+}
+
+/// Record commit parents, they are restored from branch_list.json
+fn upgrade_to_v2(repo_dir: &Path) -> Result<(), VcsError> {
+    let mut vcs_state_manager = VcsStateManager::init(repo_dir.to_path_buf());
+
+    let mut parents: HashMap<String, String> = HashMap::new();
+    for branch_data in vcs_state_manager.get_branch_list()?.branches {
+        for pair in branch_data.commits.windows(2) {
+            parents
+                .entry(pair[1].clone())
+                .or_insert_with(|| pair[0].clone());
+        }
+    }
+
+    let mut commit_list = vcs_state_manager.get_commit_list()?;
+    for commit_data in commit_list.commits.iter_mut() {
+        if commit_data.parents.is_empty() {
+            if let Some(parent) = parents.get(&commit_data.hash) {
+                commit_data.parents.push(parent.clone());
+            }
+        }
+    }
+    vcs_state_manager.set_commit_list(commit_list)
+}
+
+/// Normalize the modes of files in snapshots, the executable bit is hashed from version 3
+///
+/// Files in older snapshots keep the modes they were copied with. Symlinks were
+/// followed, so their targets stay stored as files.
+fn upgrade_to_v3(repo_dir: &Path) -> Result<(), VcsError> {
+    let mut vcs_state_manager = VcsStateManager::init(repo_dir.to_path_buf());
+
+    for commit_data in vcs_state_manager.get_commit_list()?.commits {
+        let contents = vcs_state_manager.get_commit_contents(&commit_data.hash)?;
+        for entry in contents.iter() {
+            if let EntryKind::File { executable } = get_entry_kind(entry)? {
+                set_executable(entry, executable)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Upgrade the repository to the current format version
+///
+/// The layout is migrated step by step, then the snapshot hashes and commit ids
+/// of repositories older than version 4 are computed once with the current
+/// scheme. Snapshots stay one directory per commit.
+pub fn upgrade_in_repo(repo_dir: PathBuf) -> Result<UpgradeResult, VcsError> {
+    if !repo_dir.join(".vcs").is_dir() {
+        return Err(VcsError::NotARepository);
+    }
+
+    let from = get_format(&repo_dir)?.version;
+    if from == FORMAT_VERSION {
+        return Ok(UpgradeResult::UpToDate { version: from });
+    }
+    if from > FORMAT_VERSION {
+        return Err(VcsError::UnsupportedFormat { version: from });
+    }
+
+    let backup = backup_vcs_dir(&repo_dir, from)?;
+    let mut version = from;
+    while version < FORMAT_VERSION {
+        match version {
+            1 => upgrade_to_v2(&repo_dir)?,
+            2 => upgrade_to_v3(&repo_dir)?,
+            // Paths are hashed by their bytes, the commits are rehashed below
+            3 => {}
+            // Large objects are new, older snapshots contain no pointers
+            4 => {}
+            // Older repositories use SHA-1, which is the default algorithm
+            5 => {}
+            _ => return Err(VcsError::UnsupportedFormat { version }),
+        }
+        version += 1;
+    }
+    if from < 4 {
+        rehash_commits(&repo_dir)?;
+    }
+    set_format(
+        &repo_dir,
+        FormatData {
+            version,
+            hash: HashAlgorithm::Sha1,
+        },
+    )?;
+
+    Ok(UpgradeResult::Upgraded {
+        from,
+        to: version,
+        backup,
+    })
+}
+
+pub fn upgrade() -> Result<UpgradeResult, VcsError> {
+    let repo_dir = get_repo_dir()?;
+    upgrade_in_repo(repo_dir)
+}
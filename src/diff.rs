@@ -0,0 +1,318 @@
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DiffLine {
+    Equal { old: usize, new: usize },
+    Removed { old: usize },
+    Added { new: usize },
+}
+
+/// Split data into lines, every line keeps its line break
+pub fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split_inclusive(|x| *x == b'\n').collect()
+}
+
+/// Check if the data looks like a binary file
+pub fn is_binary(data: &[u8]) -> bool {
+    data.contains(&0)
+}
+
+/// Get the shortest edit script between two sequences using Myers' algorithm
+///
+/// The linear space variant is used, which splits the sequences at the middle
+/// snake of the script and diffs both halves.
+pub fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffLine> {
+    let mut lines: Vec<DiffLine> = Vec::new();
+    diff_range(old, new, 0, 0, &mut lines);
+    lines
+}
+
+/// Append the shortest edit script of two slices starting at the given positions
+fn diff_range<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    old_start: usize,
+    new_start: usize,
+    lines: &mut Vec<DiffLine>,
+) {
+    let prefix = old.iter().zip(new).take_while(|(x, y)| x == y).count();
+    lines.extend((0..prefix).map(|i| DiffLine::Equal {
+        old: old_start + i,
+        new: new_start + i,
+    }));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let (old_start, new_start) = (old_start + prefix, new_start + prefix);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() {
+        lines.extend((0..new.len()).map(|i| DiffLine::Added { new: new_start + i }));
+    } else if new.is_empty() {
+        lines.extend((0..old.len()).map(|i| DiffLine::Removed { old: old_start + i }));
+    } else {
+        let (x_start, y_start, x_end, y_end) = find_middle_snake(old, new);
+        diff_range(
+            &old[..x_start],
+            &new[..y_start],
+            old_start,
+            new_start,
+            lines,
+        );
+        lines.extend((0..x_end - x_start).map(|i| DiffLine::Equal {
+            old: old_start + x_start + i,
+            new: new_start + y_start + i,
+        }));
+        diff_range(
+            &old[x_end..],
+            &new[y_end..],
+            old_start + x_end,
+            new_start + y_end,
+            lines,
+        );
+    }
+
+    lines.extend((0..suffix).map(|i| DiffLine::Equal {
+        old: old_start + old.len() + i,
+        new: new_start + new.len() + i,
+    }));
+}
+
+/// Find the snake in the middle of the shortest edit script of two non-empty sequences
+///
+/// Paths are searched from the start and from the end until they overlap.
+/// Returns the start and the end of the snake as positions in both sequences.
+fn find_middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize, usize, usize) {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+
+    // Furthest x on each diagonal, the backward search works on the reversed sequences
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    let index = |k: isize| (k + offset) as usize;
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+            let reverse_k = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[index(reverse_k)] >= n {
+                return (x_start as usize, y_start as usize, x as usize, y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)]) {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && old[(n - 1 - x) as usize] == new[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(k)] = x;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[index(forward_k)] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x_start) as usize,
+                    (m - y_start) as usize,
+                );
+            }
+        }
+    }
+    unreachable!("the searches overlap after at most (n + m + 1) / 2 steps")
+}
+
+/// Format the difference between two texts as unified diff hunks
+pub fn unified_diff(old: &[&[u8]], new: &[&[u8]], context: usize) -> Vec<String> {
+    let lines = diff_lines(old, new);
+    let is_equal = |line: &DiffLine| matches!(line, DiffLine::Equal { .. });
+
+    let mut old_positions: Vec<usize> = Vec::new();
+    let mut new_positions: Vec<usize> = Vec::new();
+    let mut old_position = 0;
+    let mut new_position = 0;
+    for line in lines.iter() {
+        old_positions.push(old_position);
+        new_positions.push(new_position);
+        match line {
+            DiffLine::Equal { .. } => {
+                old_position += 1;
+                new_position += 1;
+            }
+            DiffLine::Removed { .. } => old_position += 1,
+            DiffLine::Added { .. } => new_position += 1,
+        }
+    }
+
+    let mut result: Vec<String> = Vec::new();
+    let mut start = 0;
+    while let Some(first_change) = (start..lines.len()).find(|x| !is_equal(&lines[*x])) {
+        let mut end = first_change;
+        loop {
+            while end < lines.len() && !is_equal(&lines[end]) {
+                end += 1;
+            }
+            let mut next_change = end;
+            while next_change < lines.len() && is_equal(&lines[next_change]) {
+                next_change += 1;
+            }
+            if next_change < lines.len() && next_change - end <= 2 * context {
+                end = next_change;
+            } else {
+                break;
+            }
+        }
+
+        let hunk_start = first_change.saturating_sub(context).max(start);
+        let hunk_end = (end + context).min(lines.len());
+        let hunk = &lines[hunk_start..hunk_end];
+        let old_count = hunk
+            .iter()
+            .filter(|x| !matches!(x, DiffLine::Added { .. }))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|x| !matches!(x, DiffLine::Removed { .. }))
+            .count();
+        let old_start = old_positions[hunk_start] + usize::from(old_count > 0);
+        let new_start = new_positions[hunk_start] + usize::from(new_count > 0);
+        result.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_count, new_start, new_count
+        ));
+
+        for line in hunk {
+            let (prefix, text) = match *line {
+                DiffLine::Equal { old: index, .. } => (' ', old[index]),
+                DiffLine::Removed { old: index } => ('-', old[index]),
+                DiffLine::Added { new: index } => ('+', new[index]),
+            };
+            let text = String::from_utf8_lossy(text);
+            result.push(format!("{}{}", prefix, text.trim_end_matches('\n')));
+        }
+        start = hunk_end;
+    }
+
+    result
+}
+
+/// Result of merging two versions of a text
+#[derive(PartialEq, Eq, Debug)]
+pub struct MergedText {
+    pub data: Vec<u8>,
+    /// Number of conflicting regions marked in the data
+    pub conflicts: usize,
+}
+
+/// Get the index of the matching line in the other version for every base line
+fn get_base_matches(lines: &[DiffLine], base_len: usize) -> Vec<Option<usize>> {
+    let mut matches: Vec<Option<usize>> = vec![None; base_len];
+    for line in lines {
+        if let DiffLine::Equal { old, new } = *line {
+            matches[old] = Some(new);
+        }
+    }
+    matches
+}
+
+/// Append lines making sure the last one ends with a line break
+fn push_conflict_lines(data: &mut Vec<u8>, lines: &[&[u8]]) {
+    for line in lines {
+        data.extend_from_slice(line);
+    }
+    if !data.is_empty() && data[data.len() - 1] != b'\n' {
+        data.push(b'\n');
+    }
+}
+
+/// Merge the changes made in two versions of a common base text
+///
+/// Regions changed differently in both versions are written between conflict
+/// markers labeled with `ours_label` and `theirs_label`.
+pub fn merge_lines(
+    base: &[&[u8]],
+    ours: &[&[u8]],
+    theirs: &[&[u8]],
+    ours_label: &str,
+    theirs_label: &str,
+) -> MergedText {
+    let ours_matches = get_base_matches(&diff_lines(base, ours), base.len());
+    let theirs_matches = get_base_matches(&diff_lines(base, theirs), base.len());
+
+    let mut data: Vec<u8> = Vec::new();
+    let mut conflicts = 0;
+    let (mut base_pos, mut ours_pos, mut theirs_pos) = (0, 0, 0);
+    loop {
+        if base_pos < base.len()
+            && ours_matches[base_pos] == Some(ours_pos)
+            && theirs_matches[base_pos] == Some(theirs_pos)
+        {
+            data.extend_from_slice(base[base_pos]);
+            base_pos += 1;
+            ours_pos += 1;
+            theirs_pos += 1;
+            continue;
+        }
+
+        let mut base_end = base_pos;
+        while base_end < base.len()
+            && (ours_matches[base_end].is_none() || theirs_matches[base_end].is_none())
+        {
+            base_end += 1;
+        }
+        let (ours_end, theirs_end) = if base_end < base.len() {
+            (
+                ours_matches[base_end].unwrap_or(ours.len()),
+                theirs_matches[base_end].unwrap_or(theirs.len()),
+            )
+        } else {
+            (ours.len(), theirs.len())
+        };
+
+        let base_chunk = &base[base_pos..base_end];
+        let ours_chunk = &ours[ours_pos..ours_end];
+        let theirs_chunk = &theirs[theirs_pos..theirs_end];
+        if ours_chunk == base_chunk {
+            theirs_chunk.iter().for_each(|x| data.extend_from_slice(x));
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            ours_chunk.iter().for_each(|x| data.extend_from_slice(x));
+        } else {
+            conflicts += 1;
+            push_conflict_lines(&mut data, &[]);
+            data.extend_from_slice(format!("<<<<<<< {}\n", ours_label).as_bytes());
+            push_conflict_lines(&mut data, ours_chunk);
+            data.extend_from_slice(b"=======\n");
+            push_conflict_lines(&mut data, theirs_chunk);
+            data.extend_from_slice(format!(">>>>>>> {}\n", theirs_label).as_bytes());
+        }
+
+        if base_end >= base.len() {
+            break;
+        }
+        base_pos = base_end;
+        ours_pos = ours_end;
+        theirs_pos = theirs_end;
+    }
+
+    MergedText { data, conflicts }
+}
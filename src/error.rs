@@ -0,0 +1,84 @@
+use std::{fmt, path::PathBuf};
+
+use crate::{json_files::FORMAT_VERSION, repo_file_manager::escape_path};
+
+/// Errors returned by VCS operations
+#[derive(Debug)]
+pub enum VcsError {
+    /// No .vcs directory was found in the given folder or any of its parents
+    NotARepository,
+    /// A file inside .vcs could not be parsed
+    CorruptMetadata { file: PathBuf },
+    /// The repository uses a format version this build cannot work with
+    UnsupportedFormat { version: u32 },
+    /// The revision does not name any known commit
+    UnknownRevision(String),
+    /// The path does not exist in the given revision
+    PathNotFound { revision: String, path: PathBuf },
+    /// The branch does not exist in branch_list.json
+    BranchNotFound(String),
+    /// The branch name is empty or contains path separators or `..`
+    InvalidBranchName(String),
+    /// The same path has been changed in both versions being combined
+    Conflict { path: PathBuf },
+    /// The search pattern is not a valid regular expression
+    InvalidPattern(String),
+    /// No program was given to run
+    EmptyCommand,
+    /// An underlying filesystem error
+    Io(std::io::Error),
+}
+
+impl fmt::Display for VcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VcsError::NotARepository => write!(f, "Not a VCS repository"),
+            VcsError::CorruptMetadata { file } => {
+                write!(f, "Corrupt repository metadata in {}", escape_path(file))
+            }
+            VcsError::UnsupportedFormat { version } => {
+                if *version < FORMAT_VERSION {
+                    write!(
+                        f,
+                        "Repository format version {} is outdated, run `vcs upgrade`",
+                        version
+                    )
+                } else {
+                    write!(f, "Repository format version {} is not supported", version)
+                }
+            }
+            VcsError::UnknownRevision(revision) => write!(f, "Unknown revision {}", revision),
+            VcsError::PathNotFound { revision, path } => {
+                write!(
+                    f,
+                    "Path {} does not exist in {}",
+                    escape_path(path),
+                    revision
+                )
+            }
+            VcsError::BranchNotFound(branch) => write!(f, "No branch {} exists", branch),
+            VcsError::InvalidBranchName(branch) => {
+                write!(f, "Invalid branch name {:?}", branch)
+            }
+            VcsError::Conflict { path } => write!(f, "Conflict in {}", escape_path(path)),
+            VcsError::InvalidPattern(error) => write!(f, "Invalid pattern: {}", error),
+            VcsError::EmptyCommand => write!(f, "No command given"),
+            VcsError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for VcsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VcsError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for VcsError {
+    fn from(error: std::io::Error) -> Self {
+        VcsError::Io(error)
+    }
+}
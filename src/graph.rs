@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use crate::json_files::CommitData;
+
+/// A line of an ASCII commit graph
+#[derive(PartialEq, Eq, Debug)]
+pub enum GraphLine {
+    /// The commit with the given index, `padding` continues the graph below it
+    Commit {
+        prefix: String,
+        padding: String,
+        index: usize,
+    },
+    /// Edges between commits
+    Edges(String),
+}
+
+/// Draw every lane as `|`, the given lane as `mark`
+fn draw_lanes(lanes: &[String], mark: Option<(usize, char)>) -> String {
+    let mut line = String::new();
+    for i in 0..lanes.len() {
+        if i > 0 {
+            line.push(' ');
+        }
+        match mark {
+            Some((index, char)) if index == i => line.push(char),
+            _ => line.push('|'),
+        }
+    }
+    line
+}
+
+/// Put the characters at the given positions, the rest is filled with spaces
+fn draw_at(chars: &[(usize, char)]) -> String {
+    let width = chars.iter().map(|x| x.0 + 1).max().unwrap_or(0);
+    let mut line = vec![' '; width];
+    for (position, char) in chars {
+        line[*position] = *char;
+    }
+    line.into_iter().collect()
+}
+
+/// Draw the removal of a lane, lanes to the right of it shift left
+///
+/// The removed lane itself is drawn joining the lane to its left if `joins` is set.
+fn draw_collapse(lanes_count: usize, removed: usize, joins: bool) -> String {
+    let mut chars: Vec<(usize, char)> = Vec::new();
+    for i in 0..lanes_count {
+        if i < removed {
+            chars.push((2 * i, '|'));
+        } else if i > removed || joins {
+            chars.push((2 * i - 1, '/'));
+        }
+    }
+    draw_at(&chars)
+}
+
+/// Draw a new lane forking to the right of the given one, lanes to the right of it shift right
+fn draw_fork(lanes_count: usize, forked: usize) -> String {
+    let mut chars: Vec<(usize, char)> = vec![(2 * forked + 1, '\\')];
+    for i in 0..lanes_count {
+        if i <= forked {
+            chars.push((2 * i, '|'));
+        } else {
+            chars.push((2 * i + 1, '\\'));
+        }
+    }
+    draw_at(&chars)
+}
+
+/// Draw the graph of the commits, which must be ordered with children before their parents
+///
+/// Parents missing from the list are not drawn, their lanes end at the child.
+pub fn draw_graph(commits: &[CommitData]) -> Vec<GraphLine> {
+    let mut lines: Vec<GraphLine> = Vec::new();
+    let mut lanes: Vec<String> = Vec::new();
+    let hashes: HashSet<&String> = commits.iter().map(|x| &x.hash).collect();
+
+    for (index, commit_data) in commits.iter().enumerate() {
+        let column = match lanes.iter().position(|x| *x == commit_data.hash) {
+            Some(column) => column,
+            None => {
+                lanes.push(commit_data.hash.clone());
+                lanes.len() - 1
+            }
+        };
+        // Several children lead to the commit, join their lanes into one
+        while let Some(other) = lanes
+            .iter()
+            .rposition(|x| *x == commit_data.hash)
+            .filter(|x| *x != column)
+        {
+            lines.push(GraphLine::Edges(draw_collapse(lanes.len(), other, true)));
+            lanes.remove(other);
+        }
+
+        let prefix = draw_lanes(&lanes, Some((column, '*')));
+        let parents: Vec<&String> = commit_data
+            .parents
+            .iter()
+            .filter(|x| hashes.contains(x))
+            .collect();
+
+        match parents.first() {
+            Some(parent) => lanes[column] = (*parent).clone(),
+            None => {
+                lanes.remove(column);
+            }
+        }
+        let padding = draw_lanes(&lanes, None);
+        lines.push(GraphLine::Commit {
+            prefix,
+            padding,
+            index,
+        });
+        if parents.is_empty() && column < lanes.len() {
+            lines.push(GraphLine::Edges(draw_collapse(
+                lanes.len() + 1,
+                column,
+                false,
+            )));
+        }
+
+        for (i, parent) in parents.iter().enumerate().skip(1) {
+            let forked = column + i - 1;
+            lines.push(GraphLine::Edges(draw_fork(lanes.len(), forked)));
+            lanes.insert(forked + 1, (*parent).clone());
+        }
+    }
+    lines
+}
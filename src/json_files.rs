@@ -1,11 +1,59 @@
-use std::fs;
-use std::path::PathBuf;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_with::formats::Flexible;
 use serde_with::TimestampMilliSeconds;
 
+use crate::{error::VcsError, hash::HashAlgorithm};
+
+/// Read and parse a JSON file from .vcs
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, VcsError> {
+    serde_json::from_str::<T>(fs::read_to_string(path)?.as_str()).map_err(|_| {
+        VcsError::CorruptMetadata {
+            file: path.to_path_buf(),
+        }
+    })
+}
+
+/// Serialize a value and write it to a JSON file in .vcs
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), VcsError> {
+    let data = serde_json::to_string(value).map_err(std::io::Error::from)?;
+    fs::write(path, data.as_bytes())?;
+    Ok(())
+}
+
+/// Version of the .vcs layout written by this build
+pub const FORMAT_VERSION: u32 = 6;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FormatData {
+    pub version: u32,
+    /// Algorithm of all hashes in the repository, recorded since version 6
+    #[serde(default)]
+    pub hash: HashAlgorithm,
+}
+
+/// Get the repository format from format.json, repositories without it have version 1
+pub fn get_format(repo_dir: &Path) -> Result<FormatData, VcsError> {
+    if !repo_dir.join(".vcs").join("format.json").exists() {
+        return Ok(FormatData {
+            version: 1,
+            hash: HashAlgorithm::Sha1,
+        });
+    }
+    read_json(&repo_dir.join(".vcs").join("format.json"))
+}
+
+/// Set the repository format to format.json
+pub fn set_format(repo_dir: &Path, format: FormatData) -> Result<(), VcsError> {
+    write_json(&repo_dir.join(".vcs").join("format.json"), &format)
+}
+
 #[derive(Serialize, Deserialize)]
 struct CommitAndBranch {
     commit: String,
@@ -19,78 +67,100 @@ impl CommitAndBranch {
 }
 
 /// Get commit and branch from state.json
-fn get_commit_and_branch(repo_dir: &PathBuf) -> Result<CommitAndBranch, std::io::Error> {
+fn get_commit_and_branch(repo_dir: &Path) -> Result<CommitAndBranch, VcsError> {
     if !repo_dir.join(".vcs").join("state.json").exists() {
         set_commit_and_branch(
             repo_dir,
             CommitAndBranch::from(String::new(), String::new()),
         )?;
     }
-    Ok(serde_json::from_str::<CommitAndBranch>(
-        fs::read_to_string(repo_dir.join(".vcs").join("state.json"))?.as_str(),
-    )
-    .unwrap())
+    read_json(&repo_dir.join(".vcs").join("state.json"))
 }
 
 /// Get the commit from state.json
-pub fn get_commit(repo_dir: &PathBuf) -> Result<String, std::io::Error> {
+pub fn get_commit(repo_dir: &Path) -> Result<String, VcsError> {
     Ok(get_commit_and_branch(repo_dir)?.commit)
 }
 
 /// Get the branch from state.json
-pub fn get_branch(repo_dir: &PathBuf) -> Result<String, std::io::Error> {
+pub fn get_branch(repo_dir: &Path) -> Result<String, VcsError> {
     Ok(get_commit_and_branch(repo_dir)?.branch)
 }
 
 /// Set commit and branch to state.json
 fn set_commit_and_branch(
-    repo_dir: &PathBuf,
+    repo_dir: &Path,
     commit_and_branch: CommitAndBranch,
-) -> Result<(), std::io::Error> {
-    fs::write(
-        repo_dir.join(".vcs").join("state.json"),
-        serde_json::to_string(&commit_and_branch)
-            .unwrap()
-            .as_bytes(),
+) -> Result<(), VcsError> {
+    write_json(
+        &repo_dir.join(".vcs").join("state.json"),
+        &commit_and_branch,
     )
 }
 
 /// Set the commit to state.json
-pub fn set_commit(repo_dir: &PathBuf, commit: &String) -> Result<(), std::io::Error> {
+pub fn set_commit(repo_dir: &Path, commit: &str) -> Result<(), VcsError> {
     set_commit_and_branch(
         repo_dir,
-        CommitAndBranch::from(commit.clone(), get_branch(repo_dir)?),
+        CommitAndBranch::from(commit.to_string(), get_branch(repo_dir)?),
     )
 }
 
 /// Set the branch to state.json
-pub fn set_branch(repo_dir: &PathBuf, branch: &String) -> Result<(), std::io::Error> {
+pub fn set_branch(repo_dir: &Path, branch: &str) -> Result<(), VcsError> {
     set_commit_and_branch(
         repo_dir,
-        CommitAndBranch::from(get_commit(repo_dir)?, branch.clone()),
+        CommitAndBranch::from(get_commit(repo_dir)?, branch.to_string()),
     )
 }
 
 #[serde_with::serde_as]
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
 pub struct CommitData {
     pub hash: String,
+    /// Hash of the snapshot contents
+    #[serde(default)]
+    pub tree: String,
+    /// Commits this commit has been created from, the first one is the main parent
+    #[serde(default)]
+    pub parents: Vec<String>,
     pub message: String,
+    /// Empty for commits created before authors were recorded
+    #[serde(default)]
+    pub author: String,
     #[serde_as(as = "TimestampMilliSeconds<String, Flexible>")]
     pub date: SystemTime,
 }
 
+/// Get the author of new commits from the VCS_AUTHOR or USER environment variables
+pub fn get_author() -> String {
+    env::var("VCS_AUTHOR")
+        .or_else(|_| env::var("USER"))
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| String::from("unknown"))
+}
+
 impl CommitData {
-    pub fn from(hash: String, message: String, time: SystemTime) -> Self {
+    pub fn from(
+        hash: String,
+        tree: String,
+        parents: Vec<String>,
+        message: String,
+        author: String,
+        time: SystemTime,
+    ) -> Self {
         Self {
             hash,
+            tree,
+            parents,
             message,
+            author,
             date: time,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct CommitList {
     pub commits: Vec<CommitData>,
 }
@@ -104,21 +174,18 @@ impl CommitList {
 }
 
 /// Get commit list from commit_list.json
-pub fn get_commit_list(repo_dir: &PathBuf) -> Result<CommitList, std::io::Error> {
+pub fn get_commit_list(repo_dir: &Path) -> Result<CommitList, VcsError> {
     if !repo_dir.join(".vcs").join("commit_list.json").exists() {
         set_commit_list(repo_dir, CommitList::new())?;
     }
-    Ok(serde_json::from_str::<CommitList>(
-        fs::read_to_string(repo_dir.join(".vcs").join("commit_list.json"))?.as_str(),
-    )
-    .unwrap())
+    read_json(&repo_dir.join(".vcs").join("commit_list.json"))
 }
 
 /// Set commit list to commit_list.json
-pub fn set_commit_list(repo_dir: &PathBuf, commits_data: CommitList) -> Result<(), std::io::Error> {
-    fs::write(
-        repo_dir.join(".vcs").join("commit_list.json"),
-        serde_json::to_string(&commits_data).unwrap().as_bytes(),
+pub fn set_commit_list(repo_dir: &Path, commits_data: CommitList) -> Result<(), VcsError> {
+    write_json(
+        &repo_dir.join(".vcs").join("commit_list.json"),
+        &commits_data,
     )
 }
 
@@ -137,7 +204,7 @@ impl BranchData {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct BranchList {
     pub branches: Vec<BranchData>,
 }
@@ -151,20 +218,259 @@ impl BranchList {
 }
 
 /// Set the branch list to branch_list.json
-pub fn set_branch_list(repo_dir: &PathBuf, branch_list: BranchList) -> Result<(), std::io::Error> {
-    fs::write(
-        repo_dir.join(".vcs").join("branch_list.json"),
-        serde_json::to_string(&branch_list).unwrap().as_bytes(),
+pub fn set_branch_list(repo_dir: &Path, branch_list: BranchList) -> Result<(), VcsError> {
+    write_json(
+        &repo_dir.join(".vcs").join("branch_list.json"),
+        &branch_list,
     )
 }
 
 /// Get the branch list from branch_list.json
-pub fn get_branch_list(repo_dir: &PathBuf) -> Result<BranchList, std::io::Error> {
+pub fn get_branch_list(repo_dir: &Path) -> Result<BranchList, VcsError> {
     if !repo_dir.join(".vcs").join("branch_list.json").exists() {
         set_branch_list(repo_dir, BranchList::new())?;
     }
-    Ok(serde_json::from_str::<BranchList>(
-        fs::read_to_string(repo_dir.join(".vcs").join("branch_list.json"))?.as_str(),
-    )
-    .unwrap())
+    read_json(&repo_dir.join(".vcs").join("branch_list.json"))
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct StashData {
+    /// Commit with the stashed files, its parent is the commit the stash has been made on
+    pub commit: String,
+    pub branch: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct StashList {
+    /// Stashes with the newest one first
+    pub stashes: Vec<StashData>,
+}
+
+/// Set the stash list to stash_list.json
+pub fn set_stash_list(repo_dir: &Path, stash_list: StashList) -> Result<(), VcsError> {
+    write_json(&repo_dir.join(".vcs").join("stash_list.json"), &stash_list)
+}
+
+/// Get the stash list from stash_list.json
+pub fn get_stash_list(repo_dir: &Path) -> Result<StashList, VcsError> {
+    if !repo_dir.join(".vcs").join("stash_list.json").exists() {
+        return Ok(StashList::default());
+    }
+    read_json(&repo_dir.join(".vcs").join("stash_list.json"))
+}
+
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ReflogEntry {
+    pub old: String,
+    pub new: String,
+    pub message: String,
+    #[serde_as(as = "TimestampMilliSeconds<String, Flexible>")]
+    pub date: SystemTime,
+}
+
+impl ReflogEntry {
+    pub fn from(old: String, new: String, message: String, time: SystemTime) -> Self {
+        Self {
+            old,
+            new,
+            message,
+            date: time,
+        }
+    }
+}
+
+/// Check that the branch name can be used as a file name in .vcs/logs/branches
+pub fn check_branch_name(branch: &str) -> Result<(), VcsError> {
+    if branch.is_empty()
+        || branch == "."
+        || branch.contains("..")
+        || branch.contains(['/', '\\', '\0'])
+    {
+        return Err(VcsError::InvalidBranchName(branch.to_string()));
+    }
+    Ok(())
+}
+
+/// Get the reflog file of the given branch or of HEAD if no branch is given
+fn get_reflog_path(repo_dir: &Path, branch: Option<&str>) -> Result<PathBuf, VcsError> {
+    let logs_dir = repo_dir.join(".vcs").join("logs");
+    match branch {
+        Some(branch) => {
+            check_branch_name(branch)?;
+            Ok(logs_dir.join("branches").join(branch))
+        }
+        None => Ok(logs_dir.join("HEAD")),
+    }
+}
+
+/// Append an entry to the reflog of the given branch or of HEAD
+pub fn append_reflog(
+    repo_dir: &Path,
+    branch: Option<&str>,
+    entry: &ReflogEntry,
+) -> Result<(), VcsError> {
+    let path = get_reflog_path(repo_dir, branch)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(entry).map_err(std::io::Error::from)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Replace the reflog of the given branch or of HEAD
+pub fn set_reflog(
+    repo_dir: &Path,
+    branch: Option<&str>,
+    entries: &[ReflogEntry],
+) -> Result<(), VcsError> {
+    let mut data = String::new();
+    for entry in entries {
+        data.push_str(&serde_json::to_string(entry).map_err(std::io::Error::from)?);
+        data.push('\n');
+    }
+    fs::write(get_reflog_path(repo_dir, branch)?, data)?;
+    Ok(())
+}
+
+/// Get the names of all branches with a reflog, deleted branches keep their reflogs
+pub fn get_reflog_branches(repo_dir: &Path) -> Result<Vec<String>, VcsError> {
+    let branches_dir = repo_dir.join(".vcs").join("logs").join("branches");
+    let mut branches: Vec<String> = Vec::new();
+    if branches_dir.is_dir() {
+        for entry in fs::read_dir(branches_dir)? {
+            branches.push(entry?.file_name().to_string_lossy().to_string());
+        }
+    }
+    branches.sort();
+    Ok(branches)
+}
+
+/// Get the reflog of the given branch or of HEAD, the oldest entries go first
+pub fn get_reflog(repo_dir: &Path, branch: Option<&str>) -> Result<Vec<ReflogEntry>, VcsError> {
+    let path = get_reflog_path(repo_dir, branch)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries: Vec<ReflogEntry> = Vec::new();
+    for line in fs::read_to_string(&path)?.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        entries.push(
+            serde_json::from_str::<ReflogEntry>(line)
+                .map_err(|_| VcsError::CorruptMetadata { file: path.clone() })?,
+        );
+    }
+    Ok(entries)
+}
+
+/// State of a cherry-pick or rebase stopped on a conflict
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SequencerData {
+    /// Name of the command which has been interrupted
+    pub operation: String,
+    /// Branch being updated
+    pub branch: String,
+    /// Commit to return to on abort
+    pub orig_commit: String,
+    /// Commits left to replay, the first one is the conflicting commit
+    pub todo: Vec<String>,
+}
+
+fn get_sequencer_path(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(".vcs").join("sequencer.json")
+}
+
+/// Get the interrupted operation if there is one
+pub fn get_sequencer(repo_dir: &Path) -> Result<Option<SequencerData>, VcsError> {
+    let path = get_sequencer_path(repo_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_json(&path).map(Some)
+}
+
+/// Save the interrupted operation or remove it if None is given
+pub fn set_sequencer(repo_dir: &Path, sequencer: Option<&SequencerData>) -> Result<(), VcsError> {
+    let path = get_sequencer_path(repo_dir);
+    match sequencer {
+        Some(sequencer) => write_json(&path, sequencer),
+        None => {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// State of a bisect session
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct BisectData {
+    /// Commit to return to on reset
+    pub orig_commit: String,
+    /// Branch to return to on reset
+    pub orig_branch: String,
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    /// Commits which can not be tested
+    pub skipped: Vec<String>,
+}
+
+fn get_bisect_path(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(".vcs").join("bisect.json")
+}
+
+/// Get the bisect session if there is one
+pub fn get_bisect(repo_dir: &Path) -> Result<Option<BisectData>, VcsError> {
+    let path = get_bisect_path(repo_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_json(&path).map(Some)
+}
+
+/// Save the bisect session or remove it if None is given
+pub fn set_bisect(repo_dir: &Path, bisect: Option<&BisectData>) -> Result<(), VcsError> {
+    let path = get_bisect_path(repo_dir);
+    match bisect {
+        Some(bisect) => write_json(&path, bisect),
+        None => {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Files which are stored as large objects instead of in snapshots
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, Debug)]
+pub struct LargeFilesConfig {
+    /// Binary files larger than this in bytes are stored as large objects
+    pub threshold: Option<u64>,
+    /// Glob patterns of paths which are always stored as large objects
+    pub patterns: Vec<String>,
+}
+
+fn get_large_files_path(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(".vcs").join("large_files.json")
+}
+
+/// Get the large files configuration, nothing is stored as a large object by default
+pub fn get_large_files_config(repo_dir: &Path) -> Result<LargeFilesConfig, VcsError> {
+    let path = get_large_files_path(repo_dir);
+    if !path.exists() {
+        return Ok(LargeFilesConfig::default());
+    }
+    read_json(&path)
+}
+
+/// Set the large files configuration to large_files.json
+pub fn set_large_files_config(repo_dir: &Path, config: &LargeFilesConfig) -> Result<(), VcsError> {
+    write_json(&get_large_files_path(repo_dir), config)
 }
@@ -0,0 +1,227 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use regex::bytes::Regex;
+
+use crate::{
+    diff::is_binary,
+    error::VcsError,
+    hash::HashAlgorithm,
+    json_files::{get_format, get_large_files_config, LargeFilesConfig},
+    repo_file_manager::{
+        copy_entry, get_entry_kind, get_file_hash, get_relative_path, path_bytes, set_executable,
+        EntryKind,
+    },
+};
+
+/// First line of every pointer file
+const POINTER_HEADER: &str = "vcs large object v1";
+
+/// Pointer files are never larger than this in bytes
+const POINTER_MAX_SIZE: u64 = 256;
+
+/// Number of bytes at the start of a file which are checked for binary data
+const BINARY_CHECK_SIZE: u64 = 8000;
+
+/// Reference to a large object, stored in snapshots instead of the file
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Pointer {
+    /// Hash of the contents of the file
+    pub hash: String,
+    pub size: u64,
+}
+
+impl Pointer {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "{}\nhash {}\nsize {}\n",
+            POINTER_HEADER, self.hash, self.size
+        )
+        .into_bytes()
+    }
+
+    /// Parse the contents of a pointer file
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let data = std::str::from_utf8(data).ok()?;
+        let mut lines = data.strip_suffix('\n')?.split('\n');
+        if lines.next()? != POINTER_HEADER {
+            return None;
+        }
+        let hash = lines.next()?.strip_prefix("hash ")?;
+        let size = lines.next()?.strip_prefix("size ")?.parse().ok()?;
+        if lines.next().is_some()
+            || !hash.bytes().all(|x| x.is_ascii_hexdigit())
+            || HashAlgorithm::of_hash(hash).is_none()
+        {
+            return None;
+        }
+        Some(Pointer {
+            hash: hash.to_string(),
+            size,
+        })
+    }
+
+    /// Read the pointer if the path is a pointer file
+    pub fn read(path: &Path) -> Result<Option<Self>, VcsError> {
+        let metadata = fs::symlink_metadata(path)?;
+        if !metadata.is_file() || metadata.len() > POINTER_MAX_SIZE {
+            return Ok(None);
+        }
+        Ok(Pointer::parse(&fs::read(path)?))
+    }
+}
+
+/// Convert a glob pattern to a regular expression
+///
+/// `*` matches anything except `/`, `**` matches anything and `?` matches a single
+/// character. Patterns without `/` are matched against file names only.
+fn glob_to_regex(pattern: &str) -> Result<Regex, VcsError> {
+    let mut regex = String::from(if pattern.contains('/') { "^" } else { "(^|/)" });
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|error| VcsError::InvalidPattern(error.to_string()))
+}
+
+/// Area of the repository in .vcs/large_objects where large files are stored by their hash
+pub struct LargeObjects {
+    repo_dir: PathBuf,
+    hash_algorithm: HashAlgorithm,
+    threshold: Option<u64>,
+    patterns: Vec<Regex>,
+}
+
+impl LargeObjects {
+    pub fn open(repo_dir: &Path) -> Result<Self, VcsError> {
+        Self::with_config(repo_dir, &get_large_files_config(repo_dir)?)
+    }
+
+    pub fn with_config(repo_dir: &Path, config: &LargeFilesConfig) -> Result<Self, VcsError> {
+        let mut patterns: Vec<Regex> = Vec::new();
+        for pattern in config.patterns.iter() {
+            patterns.push(glob_to_regex(pattern)?);
+        }
+        Ok(LargeObjects {
+            repo_dir: repo_dir.to_path_buf(),
+            hash_algorithm: get_format(repo_dir)?.hash,
+            threshold: config.threshold,
+            patterns,
+        })
+    }
+
+    pub fn get_objects_dir(&self) -> PathBuf {
+        self.repo_dir.join(".vcs").join("large_objects")
+    }
+
+    pub fn get_object_path(&self, hash: &str) -> PathBuf {
+        self.get_objects_dir().join(hash)
+    }
+
+    /// Check if the file has to be stored as a large object by the configuration
+    fn is_large(&self, relative_path: &Path, path: &Path) -> Result<bool, VcsError> {
+        let relative_path = path_bytes(relative_path);
+        if self.patterns.iter().any(|x| x.is_match(&relative_path)) {
+            return Ok(true);
+        }
+        match self.threshold {
+            Some(threshold) if fs::metadata(path)?.len() > threshold => {
+                let mut start: Vec<u8> = Vec::new();
+                File::open(path)?
+                    .take(BINARY_CHECK_SIZE)
+                    .read_to_end(&mut start)?;
+                Ok(is_binary(&start))
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Get the pointer the file is stored as in snapshots
+    ///
+    /// Files inside .vcs have already been stored, so only pointer files are
+    /// large objects there. Files of the working tree are checked against the
+    /// configuration.
+    pub fn get_pointer(&self, path: &Path) -> Result<Option<Pointer>, VcsError> {
+        if !matches!(get_entry_kind(path)?, EntryKind::File { .. }) {
+            return Ok(None);
+        }
+        if let Some(pointer) = Pointer::read(path)? {
+            return Ok(Some(pointer));
+        }
+        if path.starts_with(self.repo_dir.join(".vcs")) {
+            return Ok(None);
+        }
+        let relative_path = get_relative_path(&self.repo_dir, path)?;
+        if !self.is_large(relative_path, path)? {
+            return Ok(None);
+        }
+        Ok(Some(Pointer {
+            hash: get_file_hash(self.hash_algorithm, path)?,
+            size: fs::metadata(path)?.len(),
+        }))
+    }
+
+    /// Copy the file into a snapshot, large files are stored as objects and replaced with pointers
+    pub fn snapshot(&self, entry: &Path, new_path: &Path) -> Result<(), VcsError> {
+        let pointer = match self.get_pointer(entry)? {
+            Some(pointer) => pointer,
+            None => return copy_entry(entry, new_path),
+        };
+        let object_path = self.get_object_path(&pointer.hash);
+        // Pointer files whose object has been pruned have nothing to store
+        if !object_path.exists() && Pointer::read(entry)?.is_none() {
+            fs::create_dir_all(self.get_objects_dir())?;
+            let temp_path = object_path.with_extension("tmp");
+            fs::copy(entry, &temp_path)?;
+            fs::rename(temp_path, &object_path)?;
+        }
+        fs::write(new_path, pointer.to_bytes())?;
+        if let EntryKind::File { executable } = get_entry_kind(entry)? {
+            set_executable(new_path, executable)?;
+        }
+        Ok(())
+    }
+
+    /// Write the file of the pointer, the pointer itself is written if the object is missing
+    ///
+    /// Returns whether the object has been found.
+    pub fn fetch(
+        &self,
+        pointer: &Pointer,
+        new_path: &Path,
+        executable: bool,
+    ) -> Result<bool, VcsError> {
+        let object_path = self.get_object_path(&pointer.hash);
+        let found = object_path.is_file();
+        if found {
+            fs::copy(object_path, new_path)?;
+        } else {
+            fs::write(new_path, pointer.to_bytes())?;
+        }
+        set_executable(new_path, executable)?;
+        Ok(found)
+    }
+
+    /// Copy an entry out of a snapshot, large objects are fetched in place of their pointers
+    pub fn checkout(&self, entry: &Path, new_path: &Path) -> Result<(), VcsError> {
+        match (get_entry_kind(entry)?, Pointer::read(entry)?) {
+            (EntryKind::File { executable }, Some(pointer)) => {
+                self.fetch(&pointer, new_path, executable)?;
+                Ok(())
+            }
+            _ => copy_entry(entry, new_path),
+        }
+    }
+}
@@ -1,52 +1,88 @@
 mod command_parser;
 pub mod commands;
+pub mod diff;
+pub mod error;
+pub mod graph;
+pub mod hash;
 pub mod json_files;
+pub mod large_objects;
 pub mod repo_file_manager;
+pub mod tree_merge;
 pub mod vcs_state_manager;
 
-use std::path::{Path, PathBuf};
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
 
 use chrono::{DateTime, Local};
 use clap::Parser;
-use command_parser::{Arguments, Commands};
+use command_parser::{Arguments, BisectAction, Commands, LfsAction, StashAction};
 use commands::{
-    commit::commit,
-    init::init,
-    jump::{jump_branch, jump_commit, JumpResult},
-    log::log,
+    bisect::{bisect_mark, bisect_reset, bisect_run, bisect_start, BisectMark, BisectResult},
+    blame::{blame, BlameResult},
+    cherry_pick::{cherry_pick, cherry_pick_abort, cherry_pick_continue, CherryPickResult},
+    commit::{amend, commit},
+    fsck::{fsck, FsckProblem},
+    grep::{grep, GrepMatch, GrepOptions},
+    init::init_with_hash,
+    jump::{jump_branch, jump_commit, JumpMode, JumpResult},
+    lfs::{lfs_list, lfs_prune, lfs_threshold, lfs_track, lfs_untrack, LfsResult},
+    log::{format_commit, log, LogOptions},
     merge::{merge, MergeResult},
-    new_branch::{new_branch, NewBranchResult},
+    new_branch::{new_branch, restore_branch, NewBranchResult},
+    rebase::{rebase, rebase_abort, rebase_continue, RebaseResult},
+    reflog::reflog,
+    reset::{reset, ResetMode},
+    restore::restore,
+    revert::{revert, RevertResult},
+    show::{show, ShowResult},
+    stash::{stash_apply, stash_drop, stash_list, stash_push, stash_show, StashResult},
     status::status,
+    upgrade::{upgrade, UpgradeResult},
 };
 
-use crate::repo_file_manager::FileChange;
+use crate::{
+    graph::{draw_graph, GraphLine},
+    json_files::CommitData,
+    repo_file_manager::{escape_path, FileChange},
+};
 
-/// Print file changes in git format
-fn print_file_changes(file_changes: &Vec<(FileChange, PathBuf)>) {
-    for file_change in file_changes {
-        let mut string_change = String::new();
-        match file_change.0 {
-            FileChange::Equal => {}
-            FileChange::Added => {
-                string_change = String::from("added");
-            }
-            FileChange::Modified => {
-                string_change = String::from("modified");
-            }
-            FileChange::Removed => {
-                string_change = String::from("removed");
-            }
+/// Describe the change of a single file such as `added: path`
+fn format_file_change(file_change: &FileChange, path: &Path) -> String {
+    let string_change = match file_change {
+        FileChange::Equal => "",
+        FileChange::Added => "added",
+        FileChange::Modified => "modified",
+        FileChange::Removed => "removed",
+        FileChange::Renamed { from, similarity } => {
+            return format!(
+                "renamed: {} -> {} ({}%)",
+                escape_path(from),
+                escape_path(path),
+                similarity
+            )
+        }
+        FileChange::Copied { from, similarity } => {
+            return format!(
+                "copied: {} -> {} ({}%)",
+                escape_path(from),
+                escape_path(path),
+                similarity
+            )
         }
-        println!(
-            "  {}: {}",
-            string_change,
-            file_change
-                .1
-                .clone()
-                .into_os_string()
-                .into_string()
-                .unwrap()
-        );
+        FileChange::ModeChanged { executable } => {
+            let (old, new) = if *executable { (644, 755) } else { (755, 644) };
+            return format!("mode changed: {} ({} -> {})", escape_path(path), old, new);
+        }
+    };
+    format!("{}: {}", string_change, escape_path(path))
+}
+
+/// Print file changes in git format
+fn print_file_changes(file_changes: &[(FileChange, PathBuf)]) {
+    for (file_change, path) in file_changes {
+        println!("  {}", format_file_change(file_change, path));
     }
 }
 
@@ -55,30 +91,23 @@ fn check_for_plural(word: String, count: usize) -> String {
     if count != 1 {
         word + "s"
     } else {
-        word.clone()
+        word
     }
 }
 
-/// Print numbers of modified, added and removed files
-fn print_changes_count(file_changes: &Vec<(FileChange, PathBuf)>) {
-    let mut modified_count: usize = 0;
-    let mut added_count: usize = 0;
-
+/// Print numbers of modified, added, removed, renamed and copied files
+fn print_changes_count(file_changes: &[(FileChange, PathBuf)]) {
+    let mut counts = [0usize; 5];
     for file_change in file_changes.iter() {
-        if file_change.0 == FileChange::Modified {
-            modified_count += 1;
-        }
-        if file_change.0 == FileChange::Added {
-            added_count += 1;
+        match file_change.0 {
+            FileChange::Modified | FileChange::ModeChanged { .. } => counts[0] += 1,
+            FileChange::Added => counts[1] += 1,
+            FileChange::Renamed { .. } => counts[3] += 1,
+            FileChange::Copied { .. } => counts[4] += 1,
+            _ => counts[2] += 1,
         }
     }
-
-    let counts = [
-        modified_count,
-        added_count,
-        file_changes.len() - modified_count - added_count,
-    ];
-    let names = ["modified", "added", "removed"];
+    let names = ["modified", "added", "removed", "renamed", "copied"];
 
     let mut first = true;
     for (i, count) in counts.iter().enumerate() {
@@ -96,16 +125,19 @@ fn print_changes_count(file_changes: &Vec<(FileChange, PathBuf)>) {
             }
         }
     }
-    println!("");
+    println!();
 }
 
 pub fn main() {
     let arguments = Arguments::parse();
 
     match arguments.commands {
-        Commands::Init(command) => match init(Path::new(&command.path).to_path_buf()) {
+        Commands::Init(command) => match init_with_hash(command.path.clone(), command.hash) {
             Ok(commit_hash) => {
-                println!("Initialized VCS repository in {}", command.path);
+                println!(
+                    "Initialized VCS repository in {}",
+                    escape_path(&command.path)
+                );
                 println!("Created commit:");
                 println!("[master {}] Initial commit", commit_hash);
             }
@@ -131,7 +163,28 @@ pub fn main() {
             }
         },
 
-        Commands::Commit(command) => match commit(&command.message) {
+        Commands::Commit(command) if command.amend => match amend(command.message.as_deref()) {
+            Ok(commit_result) => {
+                if commit_result.successful {
+                    println!(
+                        "[{} {}] Amended the last commit",
+                        commit_result.branch, commit_result.commit
+                    );
+                    if !commit_result.file_changes.is_empty() {
+                        print_changes_count(&commit_result.file_changes);
+                        print_file_changes(&commit_result.file_changes);
+                    }
+                } else {
+                    println!("You can amend only the last commit.\nAborting...");
+                }
+            }
+
+            Err(error) => {
+                println!("Could not amend the commit: {}", error);
+            }
+        },
+
+        Commands::Commit(command) => match commit(&command.message.unwrap_or_default()) {
             Ok(commit_result) => {
                 if commit_result.successful {
                     if !commit_result.file_changes.is_empty() {
@@ -157,9 +210,15 @@ pub fn main() {
         Commands::Jump(commands) => {
             let mut jump_result: JumpResult = JumpResult::NotFound;
             let mut is_error = false;
-            if commands.commit.is_some() {
-                let commit = commands.commit.as_ref().unwrap();
-                match jump_commit(commit) {
+            let mode = if commands.force {
+                JumpMode::Force
+            } else if commands.merge {
+                JumpMode::Merge
+            } else {
+                JumpMode::Safe
+            };
+            if let Some(commit) = &commands.commit {
+                match jump_commit(commit, mode) {
                     Ok(result) => {
                         jump_result = result;
                     }
@@ -168,9 +227,8 @@ pub fn main() {
                         is_error = true;
                     }
                 }
-            } else {
-                let branch = commands.branch.as_ref().unwrap();
-                match jump_branch(&branch) {
+            } else if let Some(branch) = &commands.branch {
+                match jump_branch(branch, mode) {
                     Ok(result) => {
                         jump_result = result;
                     }
@@ -186,10 +244,14 @@ pub fn main() {
                     JumpResult::UncommitedChanges { file_changes } => {
                         println!("Error: Your local changes to the following files should be commited or dropped:");
                         for file_change in file_changes {
-                            println!(
-                                "  {}",
-                                file_change.1.into_os_string().into_string().unwrap()
-                            );
+                            println!("  {}", escape_path(&file_change.1));
+                        }
+                    }
+
+                    JumpResult::MergeConflict { paths } => {
+                        println!("Error: Your local changes to the following files conflict with the new commit:");
+                        for path in paths {
+                            println!("  {}", escape_path(&path));
                         }
                     }
 
@@ -201,13 +263,10 @@ pub fn main() {
                     }
 
                     JumpResult::NotFound => {
-                        if commands.commit.is_some() {
-                            println!(
-                                "No commit with hash {} exists.\nAborting...",
-                                commands.commit.unwrap()
-                            );
-                        } else {
-                            println!("No branch {} exists.", commands.branch.unwrap());
+                        if let Some(commit) = commands.commit {
+                            println!("No commit with hash {} exists.\nAborting...", commit);
+                        } else if let Some(branch) = commands.branch {
+                            println!("No branch {} exists.", branch);
                         }
                     }
                 }
@@ -216,7 +275,12 @@ pub fn main() {
 
         Commands::NewBranch(command) => {
             let branch = command.name;
-            match new_branch(&branch) {
+            let result = if command.restore {
+                restore_branch(&branch)
+            } else {
+                new_branch(&branch)
+            };
+            match result {
                 Ok(new_branch_result) => match new_branch_result {
                     NewBranchResult::OnlyFromMaster => {
                         println!("Creating a new branch is possible only when you are in the master branch.\nAborting...");
@@ -232,6 +296,14 @@ pub fn main() {
                     NewBranchResult::AlreadyExists => {
                         println!("Branch branch_name already exists.\nAborting...");
                     }
+
+                    NewBranchResult::Restored { commit } => {
+                        println!("Restored branch {} at commit {}", branch, commit);
+                    }
+
+                    NewBranchResult::NotFound => {
+                        println!("No deleted branch {} found in the reflog.", branch);
+                    }
                 },
 
                 Err(error) => {
@@ -248,7 +320,7 @@ pub fn main() {
 
                 MergeResult::MergeConflict { path1, path2 } => {
                     println!("Merge confilict: file has been changed both in master and branch\n  {}\n  {}\nAborting...",
-                    path1.into_os_string().into_string().unwrap(), path2.into_os_string().into_string().unwrap());
+                    escape_path(&path1), escape_path(&path2));
                 }
 
                 MergeResult::Success {
@@ -274,27 +346,743 @@ pub fn main() {
             }
         },
 
-        Commands::Log => match log() {
-            Ok(log_result) => {
-                for (commit_data, file_changes) in log_result.commit_list {
-                    println!("commit {}", commit_data.hash);
+        Commands::Log(command) => {
+            let format = if command.oneline {
+                Some(String::from("%h %s"))
+            } else {
+                command.format
+            };
+            let options = LogOptions {
+                max_count: command.max_count,
+                since: command.since,
+                until: command.until,
+                author: command.author,
+                grep: command.grep,
+                paths: command.paths,
+                all: command.all,
+                skip_changes: format.is_some(),
+            };
+            match log(&options) {
+                Ok(log_result) => {
+                    let mut entries: Vec<Vec<String>> = Vec::new();
+                    for (commit_data, file_changes) in log_result.commit_list.iter() {
+                        let decoration = match log_result.decorations.get(&commit_data.hash) {
+                            Some(names) if command.graph => {
+                                format!(" ({})", names.join(", "))
+                            }
+                            _ => String::new(),
+                        };
+                        let mut entry: Vec<String> = Vec::new();
+                        if let Some(format) = &format {
+                            let text = format_commit(format, commit_data);
+                            entry.extend(text.lines().map(String::from));
+                            if entry.is_empty() {
+                                entry.push(String::new());
+                            }
+                            entry[0].push_str(&decoration);
+                            entries.push(entry);
+                            continue;
+                        }
+                        entry.push(format!("commit {}{}", commit_data.hash, decoration));
+                        if !commit_data.author.is_empty() {
+                            entry.push(format!("Author {}", commit_data.author));
+                        }
+                        entry.push(format!(
+                            "Date {}",
+                            DateTime::<Local>::to_rfc2822(&commit_data.date.into())
+                        ));
+                        entry.push(format!("Message {}", commit_data.message));
+                        entry.push(String::from("Changes:"));
+                        if !file_changes.is_empty() {
+                            for (file_change, path) in file_changes {
+                                entry.push(format!("  {}", format_file_change(file_change, path)));
+                            }
+                        } else {
+                            entry.push(String::from("  No changes"));
+                        }
+                        entries.push(entry);
+                    }
+
+                    if !command.graph {
+                        for line in entries.concat() {
+                            println!("{}", line);
+                        }
+                    } else {
+                        let commits: Vec<CommitData> =
+                            log_result.commit_list.into_iter().map(|x| x.0).collect();
+                        for graph_line in draw_graph(&commits) {
+                            match graph_line {
+                                GraphLine::Commit {
+                                    prefix,
+                                    padding,
+                                    index,
+                                } => {
+                                    for (i, line) in entries[index].iter().enumerate() {
+                                        let graph = if i == 0 { &prefix } else { &padding };
+                                        if graph.is_empty() {
+                                            println!("{}", line);
+                                        } else {
+                                            println!("{} {}", graph, line);
+                                        }
+                                    }
+                                }
+                                GraphLine::Edges(edges) => println!("{}", edges),
+                            }
+                        }
+                    }
+                }
+
+                Err(error) => {
+                    println!("Could not display logs: {}", error);
+                }
+            }
+        }
+
+        Commands::Fsck(command) => match fsck(command.repair) {
+            Ok(fsck_result) => {
+                if fsck_result.problems.is_empty() {
+                    println!("No problems found");
+                }
+                for (problem, repaired) in fsck_result.problems {
+                    let description = match problem {
+                        FsckProblem::CorruptSnapshot { commit } => {
+                            format!("corrupt snapshot {}", commit)
+                        }
+                        FsckProblem::CorruptCommit { commit } => {
+                            format!("corrupt commit {}", commit)
+                        }
+                        FsckProblem::DanglingSnapshot { commit } => {
+                            format!("dangling snapshot {}", commit)
+                        }
+                        FsckProblem::MissingSnapshot { commit } => {
+                            format!("missing snapshot {}", commit)
+                        }
+                        FsckProblem::InvalidParent { commit, parent } => {
+                            format!("missing parent {} of commit {}", parent, commit)
+                        }
+                        FsckProblem::MissingCommit { branch, commit } => {
+                            format!("missing commit {} in branch {}", commit, branch)
+                        }
+                        FsckProblem::InvalidStashCommit { commit } => {
+                            format!("missing stashed commit {}", commit)
+                        }
+                        FsckProblem::InvalidSequencerCommit { commit } => {
+                            format!("missing commit {} of the interrupted operation", commit)
+                        }
+                        FsckProblem::InvalidBisectCommit { commit } => {
+                            format!("missing commit {} of the bisect session", commit)
+                        }
+                        FsckProblem::InvalidStateCommit { commit } => {
+                            format!("current commit {} does not exist", commit)
+                        }
+                        FsckProblem::InvalidStateBranch { branch } => {
+                            format!("current branch {} does not exist", branch)
+                        }
+                    };
+                    if repaired {
+                        println!("  {} (repaired)", description);
+                    } else {
+                        println!("  {}", description);
+                    }
+                }
+            }
+
+            Err(error) => {
+                println!("Could not check the repository: {}", error);
+            }
+        },
+
+        Commands::Upgrade => match upgrade() {
+            Ok(UpgradeResult::UpToDate { version }) => {
+                println!("Repository format version {} is up to date", version);
+            }
+
+            Ok(UpgradeResult::Upgraded { from, to, backup }) => {
+                println!("Upgraded repository format from version {} to {}", from, to);
+                println!("Backup saved in {}", escape_path(&backup));
+            }
+
+            Err(error) => {
+                println!("Could not upgrade the repository: {}", error);
+            }
+        },
+
+        Commands::Reflog(command) => match reflog(command.branch.as_deref()) {
+            Ok(reflog_result) => {
+                let name = command.branch.unwrap_or_else(|| String::from("HEAD"));
+                for (i, entry) in reflog_result.entries.iter().enumerate() {
+                    let commit = if entry.new.is_empty() {
+                        "(deleted)"
+                    } else {
+                        entry.new.as_str()
+                    };
+                    println!("{} {}@{{{}}}: {}", commit, name, i, entry.message);
+                }
+            }
+
+            Err(error) => {
+                println!("Could not display reflog: {}", error);
+            }
+        },
+
+        Commands::Show(command) => match show(&command.revision) {
+            Ok(ShowResult::Commit {
+                commit_data,
+                file_diffs,
+            }) => {
+                println!("commit {}", commit_data.hash);
+                if !commit_data.parents.is_empty() {
+                    println!("Parents {}", commit_data.parents.join(" "));
+                }
+                if !commit_data.author.is_empty() {
+                    println!("Author {}", commit_data.author);
+                }
+                println!(
+                    "Date {}",
+                    DateTime::<Local>::to_rfc2822(&commit_data.date.into())
+                );
+                println!("Message {}", commit_data.message);
+                for file_diff in file_diffs {
+                    print_file_changes(&[(file_diff.change, file_diff.path.clone())]);
+                    if file_diff.binary {
+                        println!("Binary files differ");
+                    }
+                    for line in file_diff.lines {
+                        println!("{}", line);
+                    }
+                }
+            }
+
+            Ok(ShowResult::File { contents }) => {
+                if let Err(error) = io::stdout().write_all(&contents) {
+                    println!("Could not show the file: {}", error);
+                }
+            }
+
+            Ok(ShowResult::Directory { entries }) => {
+                for entry in entries {
+                    println!("{}", escape_path(&entry));
+                }
+            }
+
+            Err(error) => {
+                println!("Could not show {}: {}", command.revision, error);
+            }
+        },
+
+        Commands::Restore(command) => match restore(&command.source, &command.paths) {
+            Ok(restore_result) => {
+                if restore_result.file_changes.is_empty() {
+                    println!("Nothing to restore");
+                } else {
+                    println!("Restored from commit {}:", restore_result.commit);
+                    print_file_changes(&restore_result.file_changes);
+                }
+            }
+
+            Err(error) => {
+                println!("Could not restore: {}", error);
+            }
+        },
+
+        Commands::Reset(command) => {
+            let mode = if command.soft {
+                ResetMode::Soft
+            } else if command.hard {
+                ResetMode::Hard
+            } else {
+                ResetMode::Mixed
+            };
+            match reset(&command.revision, mode) {
+                Ok(reset_result) => {
                     println!(
-                        "Date {}",
-                        DateTime::<Local>::to_rfc2822(&commit_data.date.into())
+                        "Branch {} moved from {} to {}",
+                        reset_result.branch, reset_result.old_commit, reset_result.commit
                     );
-                    println!("Message {}", commit_data.message);
-                    println!("Changes:");
-                    if !file_changes.is_empty() {
-                        print_file_changes(&file_changes);
+                    if !reset_result.file_changes.is_empty() {
+                        println!("Changes left in the working tree:");
+                        print_file_changes(&reset_result.file_changes);
+                    }
+                }
+
+                Err(error) => {
+                    println!("Could not reset: {}", error);
+                }
+            }
+        }
+
+        Commands::Revert(command) => match revert(&command.revision) {
+            Ok(RevertResult::UncommitedChanges { file_changes }) => {
+                println!("Error: Your local changes to the following files should be commited or dropped:");
+                for file_change in file_changes {
+                    println!("  {}", escape_path(&file_change.1));
+                }
+            }
+
+            Ok(RevertResult::NotLastCommit) => {
+                println!("The revert is possible only when you are in the last commit of the branch.\nAborting...");
+            }
+
+            Ok(RevertResult::NothingToRevert) => {
+                println!("Nothing to revert, the changes are already undone");
+            }
+
+            Ok(RevertResult::MergeConflict { message, paths }) => {
+                println!("Merge conflict in:");
+                for path in paths {
+                    println!("  {}", escape_path(&path));
+                }
+                println!("Resolve the conflicts and commit the result with the message:");
+                println!("{}", message);
+            }
+
+            Ok(RevertResult::Success {
+                commit,
+                file_changes,
+            }) => {
+                println!(
+                    "Successfully created commit:\n[{}] Revert {}",
+                    commit, command.revision
+                );
+                print_changes_count(&file_changes);
+                print_file_changes(&file_changes);
+            }
+
+            Err(error) => {
+                println!("Could not revert {}: {}", command.revision, error);
+            }
+        },
+
+        Commands::CherryPick(command) => {
+            let cherry_pick_result = if command.continue_ {
+                cherry_pick_continue()
+            } else if command.abort {
+                cherry_pick_abort()
+            } else {
+                cherry_pick(&command.revisions)
+            };
+            match cherry_pick_result {
+                Ok(CherryPickResult::UncommitedChanges { file_changes }) => {
+                    println!("Error: Your local changes to the following files should be commited or dropped:");
+                    for file_change in file_changes {
+                        println!("  {}", escape_path(&file_change.1));
+                    }
+                }
+
+                Ok(CherryPickResult::NotLastCommit) => {
+                    println!("The cherry-pick is possible only when you are in the last commit of the branch.\nAborting...");
+                }
+
+                Ok(CherryPickResult::InProgress { operation }) => {
+                    println!("A {} is in progress, continue or abort it first", operation);
+                }
+
+                Ok(CherryPickResult::NotInProgress) => {
+                    println!("No cherry-pick in progress");
+                }
+
+                Ok(CherryPickResult::MergeConflict { commit, paths }) => {
+                    println!("Merge conflict while picking {} in:", commit);
+                    for path in paths {
+                        println!("  {}", escape_path(&path));
+                    }
+                    println!("Resolve the conflicts and run `vcs cherry-pick --continue`");
+                }
+
+                Ok(CherryPickResult::Success { commits }) => {
+                    println!("Successfully created commits:");
+                    for commit in commits {
+                        println!("  {}", commit);
+                    }
+                }
+
+                Ok(CherryPickResult::Aborted { commit }) => {
+                    println!("Cherry-pick aborted, returned to commit {}", commit);
+                }
+
+                Err(error) => {
+                    println!("Could not cherry-pick: {}", error);
+                }
+            }
+        }
+
+        Commands::Rebase(command) => {
+            let rebase_result = if command.continue_ {
+                rebase_continue()
+            } else if command.abort {
+                rebase_abort()
+            } else {
+                rebase(
+                    command.upstream.as_deref().unwrap_or_default(),
+                    command.onto.as_deref(),
+                )
+            };
+            match rebase_result {
+                Ok(RebaseResult::UncommitedChanges { file_changes }) => {
+                    println!("Error: Your local changes to the following files should be commited or dropped:");
+                    for file_change in file_changes {
+                        println!("  {}", escape_path(&file_change.1));
+                    }
+                }
+
+                Ok(RebaseResult::NotLastCommit) => {
+                    println!("The rebase is possible only when you are in the last commit of the branch.\nAborting...");
+                }
+
+                Ok(RebaseResult::InProgress { operation }) => {
+                    println!("A {} is in progress, continue or abort it first", operation);
+                }
+
+                Ok(RebaseResult::NotInProgress) => {
+                    println!("No rebase in progress");
+                }
+
+                Ok(RebaseResult::MergeConflict { commit, paths }) => {
+                    println!("Merge conflict while replaying {} in:", commit);
+                    for path in paths {
+                        println!("  {}", escape_path(&path));
+                    }
+                    println!("Resolve the conflicts and run `vcs rebase --continue`");
+                }
+
+                Ok(RebaseResult::Success {
+                    branch,
+                    commit,
+                    commits,
+                }) => {
+                    println!(
+                        "Successfully rebased {} commits, {} is now at {}",
+                        commits.len(),
+                        branch,
+                        commit
+                    );
+                }
+
+                Ok(RebaseResult::Aborted { commit }) => {
+                    println!("Rebase aborted, returned to commit {}", commit);
+                }
+
+                Err(error) => {
+                    println!("Could not rebase: {}", error);
+                }
+            }
+        }
+
+        Commands::Stash(command) => {
+            let stash_result = match command.action {
+                StashAction::Push { message } => stash_push(message.as_deref()),
+                StashAction::List => stash_list(),
+                StashAction::Show { index } => stash_show(index),
+                StashAction::Pop { index } => stash_apply(index, true),
+                StashAction::Apply { index } => stash_apply(index, false),
+                StashAction::Drop { index } => stash_drop(index),
+            };
+            match stash_result {
+                Ok(StashResult::NoLocalChanges) => {
+                    println!("No local changes to save");
+                }
+
+                Ok(StashResult::UncommitedChanges { file_changes }) => {
+                    println!("Error: Your local changes to the following files should be commited or dropped:");
+                    for file_change in file_changes {
+                        println!("  {}", escape_path(&file_change.1));
+                    }
+                }
+
+                Ok(StashResult::NotFound) => {
+                    println!("No such stash");
+                }
+
+                Ok(StashResult::Pushed {
+                    commit,
+                    file_changes,
+                }) => {
+                    println!("Saved local changes as {}", commit);
+                    print_file_changes(&file_changes);
+                }
+
+                Ok(StashResult::List { stashes }) => {
+                    for (i, (_, commit_data)) in stashes.iter().enumerate() {
+                        println!("stash@{{{}}}: {}", i, commit_data.message);
+                    }
+                }
+
+                Ok(StashResult::Show { file_changes }) => {
+                    print_changes_count(&file_changes);
+                    print_file_changes(&file_changes);
+                }
+
+                Ok(StashResult::MergeConflict { paths }) => {
+                    println!("Merge conflict in:");
+                    for path in paths {
+                        println!("  {}", escape_path(&path));
+                    }
+                    println!("The stash is kept in case you need it again");
+                }
+
+                Ok(StashResult::Applied { commit, dropped }) => {
+                    if dropped {
+                        println!("Applied and dropped {}", commit);
                     } else {
-                        println!("  No changes");
+                        println!("Applied {}", commit);
+                    }
+                }
+
+                Ok(StashResult::Dropped { commit }) => {
+                    println!("Dropped {}", commit);
+                }
+
+                Err(error) => {
+                    println!("Could not stash: {}", error);
+                }
+            }
+        }
+
+        Commands::Blame(command) => match blame(&command.path, &command.revision, command.lines) {
+            Ok(BlameResult::Binary) => {
+                println!("Could not blame a binary file");
+            }
+
+            Ok(BlameResult::InvalidRange { lines_count }) => {
+                println!(
+                    "The line range is outside of the file with {} {}",
+                    lines_count,
+                    check_for_plural(String::from("line"), lines_count)
+                );
+            }
+
+            Ok(BlameResult::Success { lines }) => {
+                let number_width = lines.last().map_or(1, |x| x.line_number.to_string().len());
+                let author_width = lines.iter().map(|x| x.commit_data.author.len()).max();
+                for line in lines {
+                    let date = DateTime::<Local>::from(line.commit_data.date);
+                    print!(
+                        "{} ({:author_width$} {} {:>number_width$}) ",
+                        &line.commit_data.hash[..7],
+                        line.commit_data.author,
+                        date.format("%Y-%m-%d"),
+                        line.line_number,
+                        author_width = author_width.unwrap_or_default(),
+                    );
+                    let mut data = line.data;
+                    if !data.ends_with(b"\n") {
+                        data.push(b'\n');
+                    }
+                    if let Err(error) = io::stdout().write_all(&data) {
+                        println!("Could not blame the file: {}", error);
                     }
                 }
             }
 
             Err(error) => {
-                println!("Could not display logs: {}", error);
+                println!("Could not blame the file: {}", error);
             }
         },
+
+        Commands::Bisect(command) => {
+            let bisect_result = match command.action {
+                BisectAction::Start { bad, good } => bisect_start(bad.as_deref(), &good),
+                BisectAction::Good { revision } => {
+                    bisect_mark(BisectMark::Good, revision.as_deref())
+                }
+                BisectAction::Bad { revision } => bisect_mark(BisectMark::Bad, revision.as_deref()),
+                BisectAction::Skip { revision } => {
+                    bisect_mark(BisectMark::Skip, revision.as_deref())
+                }
+                BisectAction::Reset => bisect_reset(),
+                BisectAction::Run { command } => bisect_run(&command),
+            };
+            match bisect_result {
+                Ok(BisectResult::UncommitedChanges { file_changes }) => {
+                    println!("Error: Your local changes to the following files should be commited or dropped:");
+                    for file_change in file_changes {
+                        println!("  {}", escape_path(&file_change.1));
+                    }
+                }
+
+                Ok(BisectResult::InProgress) => {
+                    println!("A bisect is already in progress, reset it first");
+                }
+
+                Ok(BisectResult::NotInProgress) => {
+                    println!("No bisect in progress");
+                }
+
+                Ok(BisectResult::Waiting { has_bad, has_good }) => {
+                    if !has_bad {
+                        println!("Waiting for a bad commit");
+                    }
+                    if !has_good {
+                        println!("Waiting for a good commit");
+                    }
+                }
+
+                Ok(BisectResult::GoodAfterBad) => {
+                    println!("The bad commit is an ancestor of a good commit");
+                }
+
+                Ok(BisectResult::Jumped { commit, remaining }) => {
+                    println!(
+                        "Bisecting: {} {} left to test after this",
+                        remaining,
+                        check_for_plural(String::from("commit"), remaining)
+                    );
+                    println!("Jumped to commit {}", commit);
+                }
+
+                Ok(BisectResult::Found { commit_data }) => {
+                    println!("{} is the first bad commit", commit_data.hash);
+                    if !commit_data.author.is_empty() {
+                        println!("Author {}", commit_data.author);
+                    }
+                    println!(
+                        "Date {}",
+                        DateTime::<Local>::to_rfc2822(&commit_data.date.into())
+                    );
+                    println!("Message {}", commit_data.message);
+                }
+
+                Ok(BisectResult::OnlySkipped { commits }) => {
+                    println!("There are only skipped commits left to test");
+                    println!("The first bad commit could be any of:");
+                    for commit in commits {
+                        println!("  {}", commit);
+                    }
+                }
+
+                Ok(BisectResult::RunFailed { commit, code }) => match code {
+                    Some(code) => println!(
+                        "Bisect run failed: the command has exited with code {} on commit {}",
+                        code, commit
+                    ),
+                    None => println!(
+                        "Bisect run failed: the command was killed by a signal on commit {}",
+                        commit
+                    ),
+                },
+
+                Ok(BisectResult::Reset { commit, branch }) => {
+                    println!(
+                        "Successfully jumped to commit {}. Current branch: {}.",
+                        commit, branch
+                    );
+                }
+
+                Err(error) => {
+                    println!("Could not bisect: {}", error);
+                }
+            }
+        }
+
+        Commands::Grep(command) => {
+            let options = GrepOptions {
+                ignore_case: command.ignore_case,
+                paths: command.paths,
+            };
+            match grep(&command.pattern, &command.revisions, &options) {
+                Ok(grep_result) => {
+                    let mut stdout = io::stdout();
+                    for grep_match in grep_result.matches {
+                        let result = match grep_match {
+                            GrepMatch::Line {
+                                revision,
+                                path,
+                                line_number,
+                                line,
+                            } => {
+                                let mut prefix = String::new();
+                                if let Some(revision) = revision {
+                                    prefix.push_str(&format!("{}:", revision));
+                                }
+                                prefix.push_str(&format!("{}:", escape_path(&path)));
+                                if command.line_number {
+                                    prefix.push_str(&format!("{}:", line_number));
+                                }
+                                stdout
+                                    .write_all(prefix.as_bytes())
+                                    .and_then(|_| stdout.write_all(&line))
+                                    .and_then(|_| stdout.write_all(b"\n"))
+                            }
+                            GrepMatch::Binary { revision, path } => match revision {
+                                Some(revision) => writeln!(
+                                    stdout,
+                                    "Binary file {}:{} matches",
+                                    revision,
+                                    escape_path(&path)
+                                ),
+                                None => {
+                                    writeln!(stdout, "Binary file {} matches", escape_path(&path))
+                                }
+                            },
+                        };
+                        if let Err(error) = result {
+                            println!("Could not print the match: {}", error);
+                            break;
+                        }
+                    }
+                }
+
+                Err(error) => {
+                    println!("Could not search: {}", error);
+                }
+            }
+        }
+
+        Commands::Lfs(command) => {
+            let lfs_result = match &command.action {
+                LfsAction::Track { pattern } => lfs_track(pattern),
+                LfsAction::Untrack { pattern } => lfs_untrack(pattern),
+                LfsAction::Threshold { size } => lfs_threshold(*size),
+                LfsAction::List { revision } => lfs_list(revision),
+                LfsAction::Prune => lfs_prune(),
+            };
+            match lfs_result {
+                Ok(LfsResult::Config { config }) => {
+                    match config.threshold {
+                        Some(threshold) => {
+                            println!("Binary files larger than {} bytes are large", threshold)
+                        }
+                        None => println!("No size threshold"),
+                    }
+                    if config.patterns.is_empty() {
+                        println!("No tracked patterns");
+                    } else {
+                        println!("Tracked patterns:");
+                        for pattern in config.patterns {
+                            println!("  {}", pattern);
+                        }
+                    }
+                }
+
+                Ok(LfsResult::NotTracked) => {
+                    println!("The pattern is not tracked");
+                }
+
+                Ok(LfsResult::List { files }) => {
+                    for file in files {
+                        println!(
+                            "{} {} {} bytes{}",
+                            &file.pointer.hash[..7],
+                            escape_path(&file.path),
+                            file.pointer.size,
+                            if file.present { "" } else { " (pruned)" }
+                        );
+                    }
+                }
+
+                Ok(LfsResult::Pruned { objects, bytes }) => {
+                    println!(
+                        "Removed {} large {}, {} bytes",
+                        objects,
+                        check_for_plural(String::from("object"), objects),
+                        bytes
+                    );
+                }
+
+                Err(error) => {
+                    println!("Could not manage large files: {}", error);
+                }
+            }
+        }
     }
 }
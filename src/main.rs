@@ -1,5 +1,3 @@
-use vcs;
-
 fn main() {
     vcs::main()
 }
@@ -1,65 +1,119 @@
-use sha1::{Digest, Sha1};
 use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
     env,
     ffi::OsString,
     fs::{self, File},
-    io::{BufReader, Error, Read},
-    path::{Component, PathBuf},
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 use walkdir::WalkDir;
 
+use crate::{
+    diff::{is_binary, split_lines},
+    error::VcsError,
+    hash::HashAlgorithm,
+    json_files::get_format,
+    large_objects::{LargeObjects, Pointer},
+};
+
+/// Lowest similarity in percent of an added file to a removed or an existing one
+/// for the change to be reported as a rename or a copy
+pub const SIMILARITY_THRESHOLD: u8 = 50;
+
+/// Files larger than this in bytes are only detected as renames or copies if they are unchanged
+pub const SIMILARITY_MAX_SIZE: u64 = 1 << 20;
+
+/// Files with more lines than this are only detected as renames or copies if they are unchanged
+pub const SIMILARITY_MAX_LINES: usize = 1 << 14;
+
+/// Size of the chunks in which files are compared
+const CHUNK_SIZE: usize = 1 << 16;
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum FileChange {
     Equal,
     Added,
     Modified,
     Removed,
+    /// The file has been moved, like removed files `from` is inside of the changed folder
+    Renamed {
+        from: PathBuf,
+        similarity: u8,
+    },
+    /// The file has been created from another file, which still exists
+    Copied {
+        from: PathBuf,
+        similarity: u8,
+    },
+    /// Only the executable bit of the file has changed
+    ModeChanged {
+        executable: bool,
+    },
+}
+
+/// Kind of an entry as it is recorded in snapshots, symlinks are never followed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum EntryKind {
+    Dir,
+    File { executable: bool },
+    Symlink { target: PathBuf },
+}
+
+impl FileChange {
+    /// Make the source of a rename or a copy relative to the folder
+    pub fn relative_to(self, dir: &Path) -> Result<FileChange, VcsError> {
+        Ok(match self {
+            FileChange::Renamed { from, similarity } => FileChange::Renamed {
+                from: strip_prefix(&from, dir)?.to_path_buf(),
+                similarity,
+            },
+            FileChange::Copied { from, similarity } => FileChange::Copied {
+                from: strip_prefix(&from, dir)?.to_path_buf(),
+                similarity,
+            },
+            change => change,
+        })
+    }
 }
 
 /// Hash a slice
-fn hash(data_to_hash: &[u8]) -> String {
-    let mut hasher = Sha1::new();
+fn hash(hash_algorithm: HashAlgorithm, data_to_hash: &[u8]) -> String {
+    let mut hasher = hash_algorithm.hasher();
     hasher.update(data_to_hash);
-    let result = hasher.finalize();
-    format!("{:x}", result)
+    hasher.finalize()
 }
 
 /// Get the repo dir from any folder inside of it
-pub fn get_repo_dir() -> Result<PathBuf, std::io::Error> {
-    let mut cur_dir = env::current_dir().unwrap();
+pub fn get_repo_dir() -> Result<PathBuf, VcsError> {
+    let mut cur_dir = env::current_dir()?;
     while !cur_dir.join(".vcs").as_path().exists() {
         if !cur_dir.pop() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Not a VCS repository",
-            ));
+            return Err(VcsError::NotARepository);
         }
     }
     Ok(cur_dir)
 }
 
 /// Get all folders and files from the given path
-pub fn get_contents(path: &PathBuf, ignore_vcs: bool) -> Result<Vec<PathBuf>, std::io::Error> {
+pub fn get_contents(path: &Path, ignore_vcs: bool) -> Result<Vec<PathBuf>, VcsError> {
     let mut contents: Vec<PathBuf> = Vec::new();
+    // A symlink is an entry of its own even if it points to a folder
+    if fs::symlink_metadata(path)?.file_type().is_symlink() {
+        contents.push(path.to_path_buf());
+        return Ok(contents);
+    }
     for entry in WalkDir::new(path) {
-        match entry {
-            Ok(dir_entry) => {
-                if !ignore_vcs
-                    || dir_entry
-                        .path()
-                        .components()
-                        .find(|x| *x == Component::Normal(".vcs".as_ref()))
-                        .is_none()
-                {
-                    contents.push(dir_entry.path().to_path_buf());
-                }
-            }
-            Err(error) => {
-                return Err(Error::new(
-                    error.io_error().unwrap().kind(),
-                    error.to_string(),
-                ));
-            }
+        let dir_entry = entry.map_err(std::io::Error::from)?;
+        if !ignore_vcs
+            || !dir_entry
+                .path()
+                .components()
+                .any(|x| x == Component::Normal(".vcs".as_ref()))
+        {
+            contents.push(dir_entry.path().to_path_buf());
         }
     }
 
@@ -67,106 +121,325 @@ pub fn get_contents(path: &PathBuf, ignore_vcs: bool) -> Result<Vec<PathBuf>, st
 }
 
 /// Add a slice to the hash
-fn add_to_hash(cur_hash: &String, to_add: &[u8]) -> String {
-    hash([cur_hash.as_bytes(), to_add].concat().as_slice())
+fn add_to_hash(hash_algorithm: HashAlgorithm, cur_hash: &str, to_add: &[u8]) -> String {
+    hash(
+        hash_algorithm,
+        [cur_hash.as_bytes(), to_add].concat().as_slice(),
+    )
+}
+
+/// Add the contents of the file to the hash, the file is read in chunks
+fn add_file_to_hash(
+    hash_algorithm: HashAlgorithm,
+    cur_hash: &str,
+    path: &Path,
+) -> Result<String, VcsError> {
+    let mut hasher = hash_algorithm.hasher();
+    hasher.update(cur_hash.as_bytes());
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+/// Get the hash of the contents of the file
+pub fn get_file_hash(hash_algorithm: HashAlgorithm, path: &Path) -> Result<String, VcsError> {
+    add_file_to_hash(hash_algorithm, "", path)
+}
+
+/// Read until the buffer is full or the end of the file is reached
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, VcsError> {
+    let mut size = 0;
+    while size < buf.len() {
+        match reader.read(&mut buf[size..]) {
+            Ok(0) => break,
+            Ok(read) => size += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(size)
+}
+
+/// Get the path relative to the repository, paths inside commits are relative to their commit
+pub fn get_relative_path<'a>(repo_dir: &Path, entry: &'a Path) -> Result<&'a Path, VcsError> {
+    let mut relative_dir = strip_prefix(entry, repo_dir)?;
+    let mut relative_dir_components = relative_dir.components();
+    if relative_dir_components.next() == Some(Component::Normal(&OsString::from(".vcs"))) {
+        relative_dir_components.next();
+        relative_dir_components.next();
+        relative_dir = relative_dir_components.as_path();
+    }
+    Ok(relative_dir)
+}
+
+/// Get the bytes of a relative path joined with `/`, so hashes are equal on every platform
+pub fn path_bytes(path: &Path) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for (i, component) in path.components().enumerate() {
+        if i > 0 {
+            bytes.push(b'/');
+        }
+        bytes.extend_from_slice(component.as_os_str().as_encoded_bytes());
+    }
+    bytes
+}
+
+/// Escape the path for printing without losing any of its bytes
+///
+/// Paths with bytes which are not valid UTF-8, control characters, quotes or
+/// backslashes are quoted, these characters are escaped like in C strings and
+/// other bytes are escaped as octal numbers.
+pub fn escape_path(path: &Path) -> String {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    match std::str::from_utf8(bytes) {
+        Ok(path)
+            if !path
+                .chars()
+                .any(|c| c.is_control() || c == '"' || c == '\\') =>
+        {
+            return path.to_string()
+        }
+        _ => {}
+    }
+
+    let mut escaped = String::from("\"");
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\t' => escaped.push_str("\\t"),
+                '\r' => escaped.push_str("\\r"),
+                c if c.is_control() => {
+                    for byte in c.to_string().bytes() {
+                        escaped.push_str(&format!("\\{:03o}", byte));
+                    }
+                }
+                c => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\{:03o}", byte));
+        }
+    }
+    escaped.push('"');
+    escaped
 }
 
 /// Get the hash to the given files and folders
-pub fn get_contents_hash(contents: &Vec<PathBuf>) -> Result<String, std::io::Error> {
-    let mut sorted_contents = contents.clone();
+pub fn get_contents_hash(repo_dir: &Path, contents: &[PathBuf]) -> Result<String, VcsError> {
+    let mut sorted_contents: Vec<(&Path, &PathBuf)> = Vec::new();
+    for entry in contents {
+        sorted_contents.push((get_relative_path(repo_dir, entry)?, entry));
+    }
     sorted_contents.sort();
 
+    let hash_algorithm = get_format(repo_dir)?.hash;
+    let large_objects = LargeObjects::open(repo_dir)?;
     let mut hash: String = String::from("lol");
-    for entry in sorted_contents {
-        hash = add_to_hash(&hash, format!("{:?}", entry).as_bytes());
-        if entry.is_file() {
-            hash = add_to_hash(&hash, &fs::read(entry)?);
+    for (relative_path, entry) in sorted_contents {
+        hash = add_to_hash(hash_algorithm, &hash, &path_bytes(relative_path));
+        match get_entry_kind(entry)? {
+            EntryKind::Dir => {}
+            EntryKind::File { executable } => {
+                // Large files have the same hash as their pointers in snapshots
+                hash = match large_objects.get_pointer(entry)? {
+                    Some(pointer) => add_to_hash(hash_algorithm, &hash, &pointer.to_bytes()),
+                    None => add_file_to_hash(hash_algorithm, &hash, entry)?,
+                };
+                if executable {
+                    hash = add_to_hash(hash_algorithm, &hash, b"mode 755");
+                }
+            }
+            EntryKind::Symlink { target } => {
+                let target = target.as_os_str().as_encoded_bytes();
+                hash = add_to_hash(hash_algorithm, &hash, &[b"symlink ", target].concat());
+            }
         }
     }
     Ok(hash)
 }
 
-/// Copy files to a new commit, the head directory must be included
-pub fn copy_files_to_commit(
-    repo_dir: &PathBuf,
-    contents: &Vec<PathBuf>,
-    commit: &String,
-) -> Result<(), std::io::Error> {
-    let commit_dir = (&repo_dir).join(".vcs").join("commits").join(commit);
-    for entry in contents {
-        let mut relative_dir = entry.strip_prefix(&repo_dir).unwrap();
-        let mut relative_dir_components = relative_dir.components();
-        if relative_dir_components.next() == Some(Component::Normal(&OsString::from(".vcs"))) {
-            relative_dir_components.next();
-            relative_dir_components.next();
-            relative_dir = relative_dir_components.as_path();
+/// Get the kind of the entry without following symlinks
+pub fn get_entry_kind(path: &Path) -> Result<EntryKind, VcsError> {
+    let metadata = fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+    Ok(if file_type.is_symlink() {
+        EntryKind::Symlink {
+            target: fs::read_link(path)?,
+        }
+    } else if file_type.is_dir() {
+        EntryKind::Dir
+    } else {
+        EntryKind::File {
+            executable: is_executable(&metadata),
         }
+    })
+}
 
-        let new_path = commit_dir.join(relative_dir);
-        if entry.is_file() {
-            fs::copy(&entry, new_path)?;
-        } else {
-            fs::create_dir(new_path)?;
+/// Check if the file is executable by anyone
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Set the mode of the file to 755 or 644, other permission bits are not tracked
+#[cfg(unix)]
+pub fn set_executable(path: &Path, executable: bool) -> Result<(), VcsError> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if executable { 0o755 } else { 0o644 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_executable(_path: &Path, _executable: bool) -> Result<(), VcsError> {
+    Ok(())
+}
+
+/// Create a symlink at the path pointing to the target
+#[cfg(unix)]
+pub fn create_symlink(target: &Path, path: &Path) -> Result<(), VcsError> {
+    std::os::unix::fs::symlink(target, path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn create_symlink(_target: &Path, path: &Path) -> Result<(), VcsError> {
+    Err(VcsError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("cannot create symlink {}", escape_path(&path)),
+    )))
+}
+
+/// Copy a file or a symlink or create an empty folder at the new path
+///
+/// Files get the normalized mode of `set_executable`, symlinks are copied as links.
+pub fn copy_entry(entry: &Path, new_path: &Path) -> Result<(), VcsError> {
+    match get_entry_kind(entry)? {
+        EntryKind::Dir => fs::create_dir(new_path)?,
+        EntryKind::File { executable } => {
+            fs::copy(entry, new_path)?;
+            set_executable(new_path, executable)?;
         }
+        EntryKind::Symlink { target } => create_symlink(&target, new_path)?,
+    }
+    Ok(())
+}
+
+/// Read the file or the target of the symlink, folders have no contents
+pub fn read_entry(path: &Path) -> Result<Option<Vec<u8>>, VcsError> {
+    Ok(match get_entry_kind(path)? {
+        EntryKind::Dir => None,
+        EntryKind::File { .. } => Some(fs::read(path)?),
+        EntryKind::Symlink { target } => Some(target.as_os_str().as_encoded_bytes().to_vec()),
+    })
+}
+
+/// Get the hash of a commit from its snapshot hash and metadata
+pub fn get_commit_hash(
+    hash_algorithm: HashAlgorithm,
+    tree: &str,
+    parents: &[String],
+    message: &str,
+    author: &str,
+    date: SystemTime,
+) -> String {
+    let millis = date
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_millis())
+        .unwrap_or(0);
+    add_to_hash(
+        hash_algorithm,
+        tree,
+        format!("{}\n{}\n{}\n{}", parents.join(" "), author, millis, message).as_bytes(),
+    )
+}
+
+/// Copy files to a new commit, the head directory must be included
+pub fn copy_files_to_commit(
+    repo_dir: &Path,
+    contents: &[PathBuf],
+    commit: &str,
+) -> Result<(), VcsError> {
+    let commit_dir = repo_dir.join(".vcs").join("commits").join(commit);
+    let large_objects = LargeObjects::open(repo_dir)?;
+    for entry in contents {
+        let new_path = commit_dir.join(get_relative_path(repo_dir, entry)?);
+        large_objects.snapshot(entry, &new_path)?;
     }
     Ok(())
 }
 
 /// Remove all files except for the ones in .vcs/ from the repository
-pub fn remove_repo_files(repo_dir: &PathBuf) -> Result<(), std::io::Error> {
+pub fn remove_repo_files(repo_dir: &Path) -> Result<(), VcsError> {
     let mut contents = get_contents(repo_dir, true)?;
     contents = contents[1..].to_vec();
     contents.reverse();
     for entry in contents {
-        if entry.strip_prefix(repo_dir).is_err() {
-            panic!("Tried to remove file outside of VCS repository");
-        }
-        if entry.is_file() {
-            fs::remove_file(entry)?;
-        } else {
+        strip_prefix(&entry, repo_dir)?;
+        if get_entry_kind(&entry)? == EntryKind::Dir {
             fs::remove_dir(entry)?;
+        } else {
+            fs::remove_file(entry)?;
         }
     }
     Ok(())
 }
 
 /// Copy files from the given commit, the repo folder must be empty
-pub fn copy_files_from_commit(repo_dir: &PathBuf, commit: &String) -> Result<(), std::io::Error> {
-    let commit_dir = (&repo_dir).join(".vcs").join("commits").join(commit);
+///
+/// Large objects are only read for the pointers of this commit, pointers to
+/// pruned objects are copied as they are.
+pub fn copy_files_from_commit(repo_dir: &Path, commit: &str) -> Result<(), VcsError> {
+    let commit_dir = repo_dir.join(".vcs").join("commits").join(commit);
     let commit_contents = get_contents(&commit_dir, false)?[1..].to_vec();
+    let large_objects = LargeObjects::open(repo_dir)?;
 
     for entry in commit_contents.iter() {
-        let new_path = repo_dir.join(entry.strip_prefix(&commit_dir).unwrap());
-        if entry.is_file() {
-            fs::copy(&entry, new_path)?;
-        } else {
-            fs::create_dir(new_path)?;
-        }
+        let new_path = repo_dir.join(strip_prefix(entry, &commit_dir)?);
+        large_objects.checkout(entry, &new_path)?;
     }
     Ok(())
 }
 
-/// Check if files are equal
-pub fn files_equal(path1: &PathBuf, path2: &PathBuf) -> Result<bool, std::io::Error> {
-    if path1.as_path().is_file() != path2.as_path().is_file() {
-        return Ok(false);
-    }
-    if path1.is_dir() {
-        return Ok(true);
+/// Check if files are equal, symlinks are equal if they have the same target
+///
+/// The executable bit is ignored, `get_file_changes` reports it separately.
+pub fn files_equal(path1: &Path, path2: &Path) -> Result<bool, VcsError> {
+    match (get_entry_kind(path1)?, get_entry_kind(path2)?) {
+        (EntryKind::Dir, EntryKind::Dir) => return Ok(true),
+        (EntryKind::Symlink { target: target1 }, EntryKind::Symlink { target: target2 }) => {
+            return Ok(target1 == target2)
+        }
+        (EntryKind::File { .. }, EntryKind::File { .. }) => {}
+        _ => return Ok(false),
     }
 
-    let file1 = File::open(path1)?;
-    let file2 = File::open(path2)?;
-    let mut reader1 = BufReader::new(file1);
-    let mut reader2 = BufReader::new(file2);
+    // A pointer is equal to the file of its large object
+    match (Pointer::read(path1)?, Pointer::read(path2)?) {
+        (Some(pointer), None) => return file_matches_pointer(path2, &pointer),
+        (None, Some(pointer)) => return file_matches_pointer(path1, &pointer),
+        _ => {}
+    }
 
-    const BUF_SIZE: usize = 10000;
-    let mut buf1 = [0; BUF_SIZE];
-    let mut buf2 = [0; BUF_SIZE];
+    if fs::metadata(path1)?.len() != fs::metadata(path2)?.len() {
+        return Ok(false);
+    }
+    let mut file1 = File::open(path1)?;
+    let mut file2 = File::open(path2)?;
+    let mut buf1 = vec![0; CHUNK_SIZE];
+    let mut buf2 = vec![0; CHUNK_SIZE];
     loop {
-        let size1 = reader1.read(&mut buf1)?;
-        let size2 = reader2.read(&mut buf2)?;
-        if size1 != size2 || buf1 != buf2 {
+        let size1 = read_chunk(&mut file1, &mut buf1)?;
+        let size2 = read_chunk(&mut file2, &mut buf2)?;
+        if buf1[..size1] != buf2[..size2] {
             return Ok(false);
         }
         if size1 == 0 {
@@ -177,13 +450,62 @@ pub fn files_equal(path1: &PathBuf, path2: &PathBuf) -> Result<bool, std::io::Er
     Ok(true)
 }
 
+/// Check if the file has the size and the hash of the pointer
+///
+/// The algorithm is taken from the length of the hash, so pointers of any
+/// repository can be checked.
+fn file_matches_pointer(path: &Path, pointer: &Pointer) -> Result<bool, VcsError> {
+    let hash_algorithm = match HashAlgorithm::of_hash(&pointer.hash) {
+        Some(hash_algorithm) => hash_algorithm,
+        None => return Ok(false),
+    };
+    Ok(fs::metadata(path)?.len() == pointer.size
+        && get_file_hash(hash_algorithm, path)? == pointer.hash)
+}
+
+/// Strip the prefix from the path, failing if the path lies outside of it
+pub fn strip_prefix<'a>(path: &'a Path, prefix: &Path) -> Result<&'a Path, VcsError> {
+    path.strip_prefix(prefix).map_err(|_| {
+        VcsError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} is outside of {}",
+                escape_path(path),
+                escape_path(prefix)
+            ),
+        ))
+    })
+}
+
+/// Resolve `.` and `..` components, failing if the path leaves the folder it is relative to
+pub fn normalize_relative_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(normalized)
+}
+
 /// Get all files changes in the first folder relative to the second one
+///
+/// Files are hashed with the algorithm of the repository to detect renames,
+/// so their hashes match the hashes of large object pointers.
 pub fn get_file_changes(
-    first_dir: &PathBuf,
-    contents: &Vec<PathBuf>,
-    other_dir: &PathBuf,
-    relative_to: &Vec<PathBuf>,
-) -> Result<Vec<(FileChange, PathBuf)>, std::io::Error> {
+    hash_algorithm: HashAlgorithm,
+    first_dir: &Path,
+    contents: &[PathBuf],
+    other_dir: &Path,
+    relative_to: &[PathBuf],
+) -> Result<Vec<(FileChange, PathBuf)>, VcsError> {
     let mut used_relative_to: Vec<(&PathBuf, bool)> = Vec::new();
     for entry in relative_to.iter() {
         used_relative_to.push((entry, false));
@@ -192,16 +514,23 @@ pub fn get_file_changes(
     let mut file_changes: Vec<(FileChange, PathBuf)> = Vec::new();
     for entry in contents.iter() {
         let mut cur_change = FileChange::Added;
-        let relative1 = entry.strip_prefix(first_dir).unwrap();
+        let relative1 = strip_prefix(entry, first_dir)?;
         for commit_entry in used_relative_to.iter_mut() {
-            let relative2 = commit_entry.0.strip_prefix(&other_dir).unwrap();
+            let relative2 = strip_prefix(commit_entry.0, other_dir)?;
             if relative1 == relative2 {
                 commit_entry.1 = true;
-                if files_equal(entry, commit_entry.0)? {
-                    cur_change = FileChange::Equal;
+                cur_change = if !files_equal(entry, commit_entry.0)? {
+                    FileChange::Modified
                 } else {
-                    cur_change = FileChange::Modified;
-                }
+                    match (get_entry_kind(entry)?, get_entry_kind(commit_entry.0)?) {
+                        (EntryKind::File { executable }, EntryKind::File { executable: old })
+                            if executable != old =>
+                        {
+                            FileChange::ModeChanged { executable }
+                        }
+                        _ => FileChange::Equal,
+                    }
+                };
                 break;
             }
         }
@@ -214,10 +543,253 @@ pub fn get_file_changes(
         if !commit_entry.1 {
             file_changes.push((
                 FileChange::Removed,
-                first_dir.join(commit_entry.0.strip_prefix(&other_dir).unwrap()),
+                first_dir.join(strip_prefix(commit_entry.0, other_dir)?),
             ));
         }
     }
 
-    Ok(file_changes)
+    detect_renames(
+        hash_algorithm,
+        first_dir,
+        other_dir,
+        relative_to,
+        file_changes,
+    )
+}
+
+/// Lines of a file counted by their hashes, to compare files without diffing them
+struct LineCounts {
+    counts: HashMap<u64, usize>,
+    lines: usize,
+    size: usize,
+}
+
+impl LineCounts {
+    /// Count the lines of a text file, None for binary files and files with too many lines
+    fn new(data: &[u8]) -> Option<Self> {
+        if is_binary(data) {
+            return None;
+        }
+        let lines = split_lines(data);
+        if lines.len() > SIMILARITY_MAX_LINES {
+            return None;
+        }
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for line in &lines {
+            let mut hasher = DefaultHasher::new();
+            line.hash(&mut hasher);
+            *counts.entry(hasher.finish()).or_default() += 1;
+        }
+        Some(LineCounts {
+            counts,
+            lines: lines.len(),
+            size: data.len(),
+        })
+    }
+
+    /// Get the similarity of two different files in percent by the number of common lines
+    fn similarity(&self, other: &LineCounts) -> u8 {
+        let max_len = self.size.max(other.size);
+        let min_len = self.size.min(other.size);
+        if min_len * 100 < max_len * SIMILARITY_THRESHOLD as usize {
+            return 0;
+        }
+
+        let common: usize = self
+            .counts
+            .iter()
+            .map(|(line, count)| (*count).min(other.counts.get(line).copied().unwrap_or(0)))
+            .sum();
+        // Equal files are compared by their hashes, so the result is below 100
+        (common * 200 / (self.lines + other.lines)).min(99) as u8
+    }
+}
+
+/// A file taking part in the detection of renames and copies
+struct Detectable {
+    /// Index of the change in the list of file changes
+    index: usize,
+    hash: String,
+    /// Size of the contents, for large files the size of their object
+    size: u64,
+    /// Lines of the file, None if it is binary or larger than `SIMILARITY_MAX_SIZE`
+    /// or `SIMILARITY_MAX_LINES`
+    lines: Option<LineCounts>,
+}
+
+impl Detectable {
+    /// Read the file if it is a file which is not empty, empty files are never renamed or copied
+    fn read(
+        hash_algorithm: HashAlgorithm,
+        path: &Path,
+        index: usize,
+    ) -> Result<Option<Self>, VcsError> {
+        if !matches!(get_entry_kind(path)?, EntryKind::File { .. }) {
+            return Ok(None);
+        }
+        if let Some(pointer) = Pointer::read(path)? {
+            return Ok(Some(Detectable {
+                index,
+                hash: pointer.hash,
+                size: pointer.size,
+                lines: None,
+            }));
+        }
+        let size = fs::metadata(path)?.len();
+        if size == 0 {
+            return Ok(None);
+        }
+        let (hash, lines) = if size > SIMILARITY_MAX_SIZE {
+            (get_file_hash(hash_algorithm, path)?, None)
+        } else {
+            let data = fs::read(path)?;
+            (hash(hash_algorithm, &data), LineCounts::new(&data))
+        };
+        Ok(Some(Detectable {
+            index,
+            hash,
+            size,
+            lines,
+        }))
+    }
+
+    /// Get the similarity in percent, large and binary files are only similar if they are equal
+    fn similarity(&self, other: &Detectable) -> u8 {
+        match (&self.lines, &other.lines) {
+            _ if self.hash == other.hash => 100,
+            (Some(lines1), Some(lines2)) => lines1.similarity(lines2),
+            _ => 0,
+        }
+    }
+}
+
+/// Replace pairs of removed and added files with renames and added files with copies
+///
+/// Files with equal content hashes are paired first, then the most similar pairs
+/// are taken. Copies are searched among files of the same size for equal contents
+/// and among modified files for similar contents.
+fn detect_renames(
+    hash_algorithm: HashAlgorithm,
+    first_dir: &Path,
+    other_dir: &Path,
+    relative_to: &[PathBuf],
+    mut file_changes: Vec<(FileChange, PathBuf)>,
+) -> Result<Vec<(FileChange, PathBuf)>, VcsError> {
+    let mut added: Vec<Detectable> = Vec::new();
+    for (i, (change, path)) in file_changes.iter().enumerate() {
+        if *change == FileChange::Added {
+            added.extend(Detectable::read(hash_algorithm, path, i)?);
+        }
+    }
+    if added.is_empty() {
+        return Ok(file_changes);
+    }
+
+    let mut removed: Vec<Detectable> = Vec::new();
+    let mut modified: Vec<Detectable> = Vec::new();
+    for (i, (change, path)) in file_changes.iter().enumerate() {
+        let old_path =
+            || -> Result<PathBuf, VcsError> { Ok(other_dir.join(strip_prefix(path, first_dir)?)) };
+        match change {
+            FileChange::Removed => {
+                removed.extend(Detectable::read(hash_algorithm, &old_path()?, i)?)
+            }
+            FileChange::Modified => {
+                modified.extend(Detectable::read(hash_algorithm, &old_path()?, i)?)
+            }
+            _ => {}
+        }
+    }
+
+    // Pairs of an added file and its source with the similarity
+    let mut renames: Vec<(usize, usize, u8)> = Vec::new();
+    let mut removed_hashes: HashMap<String, Vec<usize>> = HashMap::new();
+    for (j, file) in removed.iter().enumerate() {
+        removed_hashes.entry(file.hash.clone()).or_default().push(j);
+    }
+    let mut added_left: Vec<usize> = Vec::new();
+    for (i, file) in added.iter().enumerate() {
+        match removed_hashes.get_mut(&file.hash).and_then(|x| x.pop()) {
+            Some(j) => renames.push((i, j, 100)),
+            None => added_left.push(i),
+        }
+    }
+    let mut removed_left: Vec<usize> = (0..removed.len())
+        .filter(|j| !renames.iter().any(|x| x.1 == *j))
+        .collect();
+
+    let mut pairs: Vec<(u8, usize, usize)> = Vec::new();
+    for i in added_left.iter() {
+        for j in removed_left.iter() {
+            let similarity = added[*i].similarity(&removed[*j]);
+            if similarity >= SIMILARITY_THRESHOLD {
+                pairs.push((similarity, *i, *j));
+            }
+        }
+    }
+    pairs.sort_by_key(|x| Reverse(x.0));
+    for (similarity, i, j) in pairs {
+        if added_left.contains(&i) && removed_left.contains(&j) {
+            added_left.retain(|x| *x != i);
+            removed_left.retain(|x| *x != j);
+            renames.push((i, j, similarity));
+        }
+    }
+
+    // Copies of files which still exist, only files with the size of an added file are hashed
+    let mut copies: Vec<(usize, PathBuf, u8)> = Vec::new();
+    if !added_left.is_empty() {
+        let sizes: HashSet<u64> = added_left.iter().map(|i| added[*i].size).collect();
+        let mut existing_hashes: HashMap<String, PathBuf> = HashMap::new();
+        for entry in relative_to.iter() {
+            let relative_path = strip_prefix(entry, other_dir)?;
+            let path = first_dir.join(relative_path);
+            if !matches!(get_entry_kind(&path), Ok(EntryKind::File { .. }))
+                || !matches!(get_entry_kind(entry)?, EntryKind::File { .. })
+            {
+                continue;
+            }
+            let size = match Pointer::read(entry)? {
+                Some(pointer) => pointer.size,
+                None => fs::metadata(entry)?.len(),
+            };
+            if !sizes.contains(&size) {
+                continue;
+            }
+            if let Some(file) = Detectable::read(hash_algorithm, entry, 0)? {
+                existing_hashes.entry(file.hash).or_insert(path);
+            }
+        }
+        for i in added_left {
+            if let Some(from) = existing_hashes.get(&added[i].hash) {
+                copies.push((i, from.clone(), 100));
+                continue;
+            }
+            let best = modified
+                .iter()
+                .map(|file| (added[i].similarity(file), file.index))
+                .max_by_key(|x| x.0);
+            if let Some((similarity, k)) = best {
+                if similarity >= SIMILARITY_THRESHOLD {
+                    copies.push((i, file_changes[k].1.clone(), similarity));
+                }
+            }
+        }
+    }
+
+    let mut dropped: Vec<usize> = Vec::new();
+    for (i, j, similarity) in renames {
+        let from = file_changes[removed[j].index].1.clone();
+        file_changes[added[i].index].0 = FileChange::Renamed { from, similarity };
+        dropped.push(removed[j].index);
+    }
+    for (i, from, similarity) in copies {
+        file_changes[added[i].index].0 = FileChange::Copied { from, similarity };
+    }
+    Ok(file_changes
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !dropped.contains(i))
+        .map(|x| x.1)
+        .collect())
 }
@@ -0,0 +1,308 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diff::{is_binary, merge_lines, split_lines},
+    error::VcsError,
+    large_objects::{LargeObjects, Pointer},
+    repo_file_manager::{
+        create_symlink, files_equal, get_contents, get_entry_kind, read_entry, set_executable,
+        strip_prefix, EntryKind,
+    },
+};
+
+/// Files and folders of a tree relative to its root, an absent tree is empty
+struct Tree {
+    files: BTreeSet<PathBuf>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl Tree {
+    fn read(dir: Option<&Path>, ignore_vcs: bool) -> Result<Self, VcsError> {
+        let mut tree = Tree {
+            files: BTreeSet::new(),
+            dirs: BTreeSet::new(),
+        };
+        if let Some(dir) = dir {
+            for entry in get_contents(dir, ignore_vcs)?[1..].iter() {
+                let relative_path = strip_prefix(entry, dir)?.to_path_buf();
+                if get_entry_kind(entry)? == EntryKind::Dir {
+                    tree.dirs.insert(relative_path);
+                } else {
+                    tree.files.insert(relative_path);
+                }
+            }
+        }
+        Ok(tree)
+    }
+}
+
+/// A file or a symlink present in a tree, its contents are only read when they are merged
+struct Blob {
+    path: PathBuf,
+    kind: EntryKind,
+}
+
+impl Blob {
+    /// Check if both entries have the same kind and contents, large files are
+    /// equal to their pointers
+    fn equals(&self, other: &Blob) -> Result<bool, VcsError> {
+        Ok(self.kind == other.kind && files_equal(&self.path, &other.path)?)
+    }
+}
+
+/// Get the file if it is present in the tree
+fn get_file(dir: Option<&Path>, tree: &Tree, path: &Path) -> Result<Option<Blob>, VcsError> {
+    match dir {
+        Some(dir) if tree.files.contains(path) => {
+            let full_path = dir.join(path);
+            Ok(Some(Blob {
+                kind: get_entry_kind(&full_path)?,
+                path: full_path,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Check if both files are absent or equal
+fn blobs_equal(blob1: &Option<Blob>, blob2: &Option<Blob>) -> Result<bool, VcsError> {
+    match (blob1, blob2) {
+        (Some(blob1), Some(blob2)) => blob1.equals(blob2),
+        (blob1, blob2) => Ok(blob1.is_none() && blob2.is_none()),
+    }
+}
+
+/// New contents of a file in the working tree
+enum PlannedFile {
+    /// The file or symlink is copied from the other tree
+    Copy(Blob),
+    /// The result of merging the file line by line
+    Merged { data: Vec<u8>, executable: bool },
+}
+
+/// Write the file or the symlink to the repository replacing whatever is there
+fn write_file(
+    large_objects: &LargeObjects,
+    repo_dir: &Path,
+    path: &Path,
+    file: &PlannedFile,
+) -> Result<(), VcsError> {
+    let full_path = repo_dir.join(path);
+    match fs::symlink_metadata(&full_path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&full_path)?,
+        Ok(_) => fs::remove_file(&full_path)?,
+        Err(_) => {}
+    }
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match file {
+        PlannedFile::Copy(blob) => match &blob.kind {
+            EntryKind::Symlink { target } => create_symlink(target, &full_path)?,
+            EntryKind::File { executable } => match Pointer::read(&blob.path)? {
+                Some(pointer) => {
+                    large_objects.fetch(&pointer, &full_path, *executable)?;
+                }
+                None => {
+                    fs::copy(&blob.path, &full_path)?;
+                    set_executable(&full_path, *executable)?;
+                }
+            },
+            EntryKind::Dir => fs::create_dir_all(&full_path)?,
+        },
+        PlannedFile::Merged { data, executable } => {
+            fs::write(&full_path, data)?;
+            set_executable(&full_path, *executable)?;
+        }
+    }
+    Ok(())
+}
+
+/// Changes to the working tree computed by `plan_merge`
+pub struct MergePlan {
+    /// Paths relative to the repository with their new contents, None removes the file
+    files: Vec<(PathBuf, Option<PlannedFile>)>,
+    created_dirs: Vec<PathBuf>,
+    removed_dirs: Vec<PathBuf>,
+    /// Paths which will contain conflict markers or, for binary files and files
+    /// removed on one side, our version of the file
+    pub conflicts: Vec<PathBuf>,
+}
+
+impl MergePlan {
+    /// Write the planned changes to the working tree of the repository
+    pub fn apply(&self, repo_dir: &Path) -> Result<(), VcsError> {
+        let large_objects = LargeObjects::open(repo_dir)?;
+        for (path, file) in self.files.iter() {
+            match file {
+                Some(file) => write_file(&large_objects, repo_dir, path, file)?,
+                None => fs::remove_file(repo_dir.join(path))?,
+            }
+        }
+        for path in self.created_dirs.iter() {
+            fs::create_dir_all(repo_dir.join(path))?;
+        }
+        for path in self.removed_dirs.iter().rev() {
+            let full_path = repo_dir.join(path);
+            if full_path.is_dir() && fs::read_dir(&full_path)?.next().is_none() {
+                fs::remove_dir(full_path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compute the changes made between the base and their tree applied to the working tree of the repository
+///
+/// Files changed in both trees are merged line by line, nothing is written.
+pub fn plan_merge(
+    repo_dir: &Path,
+    base_dir: Option<&Path>,
+    theirs_dir: Option<&Path>,
+    ours_label: &str,
+    theirs_label: &str,
+) -> Result<MergePlan, VcsError> {
+    let base = Tree::read(base_dir, false)?;
+    let ours = Tree::read(Some(repo_dir), true)?;
+    let theirs = Tree::read(theirs_dir, false)?;
+    let large_objects = LargeObjects::open(repo_dir)?;
+
+    let mut plan = MergePlan {
+        files: Vec::new(),
+        created_dirs: theirs.dirs.difference(&base.dirs).cloned().collect(),
+        removed_dirs: base.dirs.difference(&theirs.dirs).cloned().collect(),
+        conflicts: Vec::new(),
+    };
+    let files: BTreeSet<&PathBuf> = base
+        .files
+        .iter()
+        .chain(ours.files.iter())
+        .chain(theirs.files.iter())
+        .collect();
+    for path in files {
+        let base_file = get_file(base_dir, &base, path)?;
+        let ours_file = get_file(Some(repo_dir), &ours, path)?;
+        let theirs_file = get_file(theirs_dir, &theirs, path)?;
+
+        if blobs_equal(&ours_file, &theirs_file)? || blobs_equal(&base_file, &theirs_file)? {
+            continue;
+        }
+        if blobs_equal(&base_file, &ours_file)? {
+            match theirs_file {
+                Some(blob) => {
+                    if ours.dirs.contains(path) {
+                        plan.conflicts.push(path.clone());
+                    } else {
+                        plan.files
+                            .push((path.clone(), Some(PlannedFile::Copy(blob))));
+                    }
+                }
+                None => {
+                    if ours_file.is_some() {
+                        plan.files.push((path.clone(), None));
+                    }
+                }
+            }
+            continue;
+        }
+
+        match (base_file, ours_file, theirs_file) {
+            (base_file, Some(ours_file), Some(theirs_file)) => {
+                // Symlinks changed on both sides can not be merged, ours is kept
+                let (ours_executable, theirs_executable) =
+                    match (&ours_file.kind, &theirs_file.kind) {
+                        (
+                            EntryKind::File { executable: ours },
+                            EntryKind::File { executable: theirs },
+                        ) => (*ours, *theirs),
+                        _ => {
+                            plan.conflicts.push(path.clone());
+                            continue;
+                        }
+                    };
+                let base_executable = match &base_file {
+                    Some(Blob {
+                        kind: EntryKind::File { executable },
+                        ..
+                    }) => *executable,
+                    _ => ours_executable,
+                };
+                // Large files are merged like binary files, so only the contents of
+                // files which are merged line by line are read
+                let blobs = [base_file.as_ref(), Some(&ours_file), Some(&theirs_file)];
+                let mut is_large = false;
+                for blob in blobs.iter().flatten() {
+                    is_large |= large_objects.get_pointer(&blob.path)?.is_some();
+                }
+                if is_large {
+                    plan.conflicts.push(path.clone());
+                    continue;
+                }
+                let base_data = match &base_file {
+                    Some(blob) => read_entry(&blob.path)?.unwrap_or_default(),
+                    None => Vec::new(),
+                };
+                let ours_data = read_entry(&ours_file.path)?.unwrap_or_default();
+                let theirs_data = read_entry(&theirs_file.path)?.unwrap_or_default();
+                if [&base_data, &ours_data, &theirs_data]
+                    .iter()
+                    .any(|x| is_binary(x))
+                {
+                    plan.conflicts.push(path.clone());
+                    continue;
+                }
+                let merged = merge_lines(
+                    &split_lines(&base_data),
+                    &split_lines(&ours_data),
+                    &split_lines(&theirs_data),
+                    ours_label,
+                    theirs_label,
+                );
+                let executable = if base_executable == ours_executable {
+                    theirs_executable
+                } else {
+                    ours_executable
+                };
+                plan.files.push((
+                    path.clone(),
+                    Some(PlannedFile::Merged {
+                        data: merged.data,
+                        executable,
+                    }),
+                ));
+                if merged.conflicts > 0 {
+                    plan.conflicts.push(path.clone());
+                }
+            }
+            (_, None, Some(theirs_file)) => {
+                if !ours.dirs.contains(path) {
+                    plan.files
+                        .push((path.clone(), Some(PlannedFile::Copy(theirs_file))));
+                }
+                plan.conflicts.push(path.clone());
+            }
+            _ => plan.conflicts.push(path.clone()),
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Apply the changes made between the base and their tree to the working tree of the repository
+///
+/// Returns the conflicting paths relative to the repository, see `MergePlan::conflicts`.
+pub fn merge_trees(
+    repo_dir: &Path,
+    base_dir: Option<&Path>,
+    theirs_dir: Option<&Path>,
+    ours_label: &str,
+    theirs_label: &str,
+) -> Result<Vec<PathBuf>, VcsError> {
+    let plan = plan_merge(repo_dir, base_dir, theirs_dir, ours_label, theirs_label)?;
+    plan.apply(repo_dir)?;
+    Ok(plan.conflicts)
+}
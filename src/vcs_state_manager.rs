@@ -1,13 +1,23 @@
-use std::{fs, path::PathBuf, time::SystemTime};
+use std::{
+    collections::HashSet,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
+    error::VcsError,
+    hash::HashAlgorithm,
     json_files::{
-        get_branch, get_branch_list, get_commit, get_commit_list, set_branch, set_branch_list,
-        set_commit, set_commit_list, BranchData, BranchList, CommitData, CommitList,
+        append_reflog, get_author, get_bisect, get_branch, get_branch_list, get_commit,
+        get_commit_list, get_format, get_reflog, get_sequencer, get_stash_list, set_bisect,
+        set_branch, set_branch_list, set_commit, set_commit_list, set_format, set_sequencer,
+        set_stash_list, BisectData, BranchData, BranchList, CommitData, CommitList, FormatData,
+        ReflogEntry, SequencerData, StashList, FORMAT_VERSION,
     },
     repo_file_manager::{
-        copy_files_from_commit, copy_files_to_commit, get_contents, get_contents_hash,
-        get_file_changes, remove_repo_files, FileChange,
+        copy_files_from_commit, copy_files_to_commit, get_commit_hash, get_contents,
+        get_contents_hash, get_file_changes, remove_repo_files, FileChange,
     },
 };
 
@@ -31,226 +41,546 @@ impl VcsStateManager {
         }
     }
 
-    pub fn get_commit(self: &mut Self) -> Result<String, std::io::Error> {
+    /// Open the VCS state manager of an existing repository
+    pub fn open(repo_dir: PathBuf) -> Result<Self, VcsError> {
+        if !repo_dir.join(".vcs").is_dir() {
+            return Err(VcsError::NotARepository);
+        }
+        let version = get_format(&repo_dir)?.version;
+        if version != FORMAT_VERSION {
+            return Err(VcsError::UnsupportedFormat { version });
+        }
+        Ok(Self::init(repo_dir))
+    }
+
+    pub fn get_commit(&mut self) -> Result<String, VcsError> {
         match self.cur_commit.clone() {
             Some(value) => Ok(value),
 
             None => {
-                self.cur_commit = Some(get_commit(&self.repo_dir)?);
-                Ok(self.cur_commit.clone().unwrap())
+                let commit = get_commit(&self.repo_dir)?;
+                self.cur_commit = Some(commit.clone());
+                Ok(commit)
             }
         }
     }
 
-    pub fn get_branch(self: &mut Self) -> Result<String, std::io::Error> {
+    pub fn get_branch(&mut self) -> Result<String, VcsError> {
         match self.cur_branch.clone() {
             Some(value) => Ok(value),
 
             None => {
-                self.cur_branch = Some(get_branch(&self.repo_dir)?);
-                Ok(self.cur_branch.clone().unwrap())
+                let branch = get_branch(&self.repo_dir)?;
+                self.cur_branch = Some(branch.clone());
+                Ok(branch)
             }
         }
     }
 
-    pub fn set_commit(self: &mut Self, commit: &String) -> Result<(), std::io::Error> {
-        self.cur_commit = Some(commit.clone());
-        set_commit(&self.repo_dir, commit)
+    /// Set the commit to state.json and record the move in the HEAD reflog
+    pub fn set_commit(&mut self, commit: &str, message: &str) -> Result<(), VcsError> {
+        let old_commit = self.get_commit()?;
+        self.cur_commit = Some(commit.to_string());
+        set_commit(&self.repo_dir, commit)?;
+        self.append_reflog(None, &old_commit, commit, message)
     }
 
-    pub fn set_branch(self: &mut Self, branch: &String) -> Result<(), std::io::Error> {
-        self.cur_branch = Some(branch.clone());
+    pub fn set_branch(&mut self, branch: &str) -> Result<(), VcsError> {
+        self.cur_branch = Some(branch.to_string());
         set_branch(&self.repo_dir, branch)
     }
 
     /// Get commit list from commit_list.json
-    pub fn get_commit_list(self: &mut Self) -> Result<CommitList, std::io::Error> {
+    pub fn get_commit_list(&mut self) -> Result<CommitList, VcsError> {
         match self.commit_list.clone() {
             Some(value) => Ok(value),
 
             None => {
-                self.commit_list = Some(get_commit_list(&self.repo_dir)?);
-                Ok(self.commit_list.as_ref().unwrap().clone())
+                let commit_list = get_commit_list(&self.repo_dir)?;
+                self.commit_list = Some(commit_list.clone());
+                Ok(commit_list)
             }
         }
     }
 
     /// Set commit list to commit_list.json
-    pub fn set_commit_list(self: &mut Self, commit_list: CommitList) -> Result<(), std::io::Error> {
+    pub fn set_commit_list(&mut self, commit_list: CommitList) -> Result<(), VcsError> {
         self.commit_list = Some(commit_list.clone());
         set_commit_list(&self.repo_dir, commit_list)
     }
 
     /// Add commit data to commit list from commit_list.json
-    pub fn add_commit_data(self: &mut Self, commit_data: CommitData) -> Result<(), std::io::Error> {
+    pub fn add_commit_data(&mut self, commit_data: CommitData) -> Result<(), VcsError> {
         let mut commits_data = self.get_commit_list()?;
         commits_data.commits.push(commit_data);
         self.set_commit_list(commits_data)
     }
 
     /// Get commit data by commit hash from commit_list.json
-    pub fn get_commit_data(
-        self: &mut Self,
-        commit: &String,
-    ) -> Result<Option<CommitData>, std::io::Error> {
+    pub fn get_commit_data(&mut self, commit: &str) -> Result<Option<CommitData>, VcsError> {
         let commit_list = self.get_commit_list()?;
-
-        let temp = commit_list
-            .commits
-            .iter()
-            .find(|x| x.hash == commit.clone());
-        if temp.is_none() {
-            return Ok(None);
-        } else {
-            Ok(Some(temp.unwrap().clone()))
-        }
+        Ok(commit_list.commits.into_iter().find(|x| x.hash == commit))
     }
 
-    pub fn get_branch_list(self: &mut Self) -> Result<BranchList, std::io::Error> {
+    pub fn get_branch_list(&mut self) -> Result<BranchList, VcsError> {
         match self.branch_list.clone() {
             Some(value) => Ok(value),
 
             None => {
-                self.branch_list = Some(get_branch_list(&self.repo_dir)?);
-                Ok(self.branch_list.as_ref().unwrap().clone())
+                let branch_list = get_branch_list(&self.repo_dir)?;
+                self.branch_list = Some(branch_list.clone());
+                Ok(branch_list)
             }
         }
     }
 
-    pub fn set_branch_list(self: &mut Self, branch_list: BranchList) -> Result<(), std::io::Error> {
+    pub fn set_branch_list(&mut self, branch_list: BranchList) -> Result<(), VcsError> {
         self.branch_list = Some(branch_list.clone());
         set_branch_list(&self.repo_dir, branch_list)
     }
 
+    /// Append an entry to the reflog of the given branch or of HEAD
+    fn append_reflog(
+        &self,
+        branch: Option<&str>,
+        old: &str,
+        new: &str,
+        message: &str,
+    ) -> Result<(), VcsError> {
+        append_reflog(
+            &self.repo_dir,
+            branch,
+            &ReflogEntry::from(
+                old.to_string(),
+                new.to_string(),
+                message.to_string(),
+                SystemTime::now(),
+            ),
+        )
+    }
+
     /// Add the commit to the given branch and save it to branch_list.json
     pub fn add_branch_commit(
-        self: &mut Self,
-        branch: &String,
-        commit: &String,
-    ) -> Result<(), std::io::Error> {
+        &mut self,
+        branch: &str,
+        commit: &str,
+        message: &str,
+    ) -> Result<(), VcsError> {
         let mut branch_list = self.get_branch_list()?;
-        let found = branch_list.branches.iter_mut().find(|x| x.name == *branch);
-        match found {
+        let found = branch_list.branches.iter_mut().find(|x| x.name == branch);
+        let old_commit = match found {
             Some(branch_data) => {
-                branch_data.commits.push(commit.clone());
+                let old_commit = branch_data.commits.last().cloned().unwrap_or_default();
+                branch_data.commits.push(commit.to_string());
+                old_commit
             }
             None => {
                 branch_list
                     .branches
-                    .push(BranchData::from(branch.clone(), commit.clone()));
+                    .push(BranchData::from(branch.to_string(), commit.to_string()));
+                String::new()
             }
-        }
+        };
 
-        self.set_branch_list(branch_list)
+        self.set_branch_list(branch_list)?;
+        self.append_reflog(Some(branch), &old_commit, commit, message)
     }
 
     /// Get commits of the given branch from branch_list.json
-    pub fn get_commits(
-        self: &mut Self,
-        branch: &String,
-    ) -> Result<Option<Vec<String>>, std::io::Error> {
+    pub fn get_commits(&mut self, branch: &str) -> Result<Option<Vec<String>>, VcsError> {
         let branch_list = self.get_branch_list()?;
-        let branch = branch_list.branches.into_iter().find(|x| x.name == *branch);
+        let branch = branch_list.branches.into_iter().find(|x| x.name == branch);
         match branch {
             Some(branch_data) => Ok(Some(branch_data.commits)),
             None => Ok(None),
         }
     }
 
+    /// Get commits of the given branch, failing if the branch does not exist
+    pub fn get_existing_commits(&mut self, branch: &str) -> Result<Vec<String>, VcsError> {
+        match self.get_commits(branch)? {
+            Some(commits) if !commits.is_empty() => Ok(commits),
+            _ => Err(VcsError::BranchNotFound(branch.to_string())),
+        }
+    }
+
+    /// Get the last commit of the given branch
+    pub fn get_last_commit(&mut self, branch: &str) -> Result<String, VcsError> {
+        let mut commits = self.get_existing_commits(branch)?;
+        commits
+            .pop()
+            .ok_or_else(|| VcsError::BranchNotFound(branch.to_string()))
+    }
+
+    /// Get the commits of a branch ending with the given commit from its first parents
+    ///
+    /// A branch other than master starts at the first commit which is on master.
+    fn get_first_parent_chain(
+        &mut self,
+        branch: &str,
+        commit: &str,
+    ) -> Result<Vec<String>, VcsError> {
+        let master_commits = self.get_commits("master")?.unwrap_or_default();
+        let mut commits = vec![commit.to_string()];
+        let mut cur_commit = commit.to_string();
+        while branch == "master" || !master_commits.contains(&cur_commit) {
+            match self.get_parent(&cur_commit, 0)? {
+                Some(parent) => {
+                    commits.push(parent.clone());
+                    cur_commit = parent;
+                }
+                None => break,
+            }
+        }
+        commits.reverse();
+        Ok(commits)
+    }
+
+    /// Move the tip of the branch to the given commit
+    ///
+    /// If the commit is already in the branch, the branch is truncated after it.
+    /// Otherwise the branch is rebuilt from the first parents of the commit.
+    pub fn set_branch_tip(
+        &mut self,
+        branch: &str,
+        commit: &str,
+        message: &str,
+    ) -> Result<(), VcsError> {
+        let mut branch_list = self.get_branch_list()?;
+        let branch_data = branch_list
+            .branches
+            .iter_mut()
+            .find(|x| x.name == branch)
+            .ok_or_else(|| VcsError::BranchNotFound(branch.to_string()))?;
+        let old_commit = branch_data.commits.last().cloned().unwrap_or_default();
+
+        match branch_data.commits.iter().position(|x| x == commit) {
+            Some(position) => branch_data.commits.truncate(position + 1),
+            None => branch_data.commits = self.get_first_parent_chain(branch, commit)?,
+        }
+
+        self.set_branch_list(branch_list)?;
+        self.append_reflog(Some(branch), &old_commit, commit, message)
+    }
+
     /// Remove the branch from branch_list.json
-    pub fn remove_branch(self: &mut Self, branch: &String) -> Result<(), std::io::Error> {
+    pub fn remove_branch(&mut self, branch: &str, message: &str) -> Result<(), VcsError> {
         let mut branch_list = self.get_branch_list()?;
         let found = branch_list
             .branches
             .iter_mut()
-            .position(|x| x.name == *branch);
-        if found.is_some() {
-            branch_list.branches.remove(found.unwrap());
+            .position(|x| x.name == branch);
+        if let Some(position) = found {
+            let branch_data = branch_list.branches.remove(position);
             self.set_branch_list(branch_list)?;
+            let old_commit = branch_data.commits.last().cloned().unwrap_or_default();
+            self.append_reflog(Some(branch), &old_commit, "", message)?;
         }
         Ok(())
     }
 
-    /// Initialize repository in the given path
-    pub fn init_repository(self: &mut Self) -> Result<String, std::io::Error> {
+    /// Get the reflog of the given branch or of HEAD, the oldest entries go first
+    pub fn get_reflog(&mut self, branch: Option<&str>) -> Result<Vec<ReflogEntry>, VcsError> {
+        get_reflog(&self.repo_dir, branch)
+    }
+
+    /// Rebuild a deleted branch from the tip it had when it was deleted
+    ///
+    /// The tip is taken from the reflog, the commits before it are its first
+    /// parents like after moving the tip with `set_branch_tip`. Returns the
+    /// restored last commit or None if the branch has no deleted history.
+    pub fn restore_branch(&mut self, branch: &str) -> Result<Option<String>, VcsError> {
+        let reflog = self.get_reflog(Some(branch))?;
+        let last_commit = match reflog.iter().rev().find(|x| x.new.is_empty()) {
+            Some(deletion) if !deletion.old.is_empty() => deletion.old.clone(),
+            _ => return Ok(None),
+        };
+        if self.get_commit_data(&last_commit)?.is_none() {
+            return Err(VcsError::UnknownRevision(last_commit));
+        }
+        let commits = self.get_first_parent_chain(branch, &last_commit)?;
+
+        let mut branch_list = self.get_branch_list()?;
+        branch_list.branches.push(BranchData {
+            name: branch.to_string(),
+            commits,
+        });
+        self.set_branch_list(branch_list)?;
+        self.append_reflog(
+            Some(branch),
+            "",
+            &last_commit,
+            "branch: restored from reflog",
+        )?;
+        Ok(Some(last_commit))
+    }
+
+    /// Get stashes from stash_list.json
+    pub fn get_stash_list(&self) -> Result<StashList, VcsError> {
+        get_stash_list(&self.repo_dir)
+    }
+
+    /// Set stashes to stash_list.json
+    pub fn set_stash_list(&self, stash_list: StashList) -> Result<(), VcsError> {
+        set_stash_list(&self.repo_dir, stash_list)
+    }
+
+    /// Get the cherry-pick or rebase stopped on a conflict
+    pub fn get_sequencer(&self) -> Result<Option<SequencerData>, VcsError> {
+        get_sequencer(&self.repo_dir)
+    }
+
+    /// Save the interrupted operation or remove it if None is given
+    pub fn set_sequencer(&self, sequencer: Option<&SequencerData>) -> Result<(), VcsError> {
+        set_sequencer(&self.repo_dir, sequencer)
+    }
+
+    /// Get the bisect session if there is one
+    pub fn get_bisect(&self) -> Result<Option<BisectData>, VcsError> {
+        get_bisect(&self.repo_dir)
+    }
+
+    /// Save the bisect session or remove it if None is given
+    pub fn set_bisect(&self, bisect: Option<&BisectData>) -> Result<(), VcsError> {
+        set_bisect(&self.repo_dir, bisect)
+    }
+
+    /// Resolve a revision to a commit hash
+    ///
+    /// Supported revisions are commit hashes or their unique prefixes, branch
+    /// names, HEAD, reflog positions such as `@{1}`, `HEAD@{2}` or
+    /// `master@{1}` and stashes such as `stash@{0}`. Any of them may be
+    /// followed by `~n` to take the n-th first parent or by `^n` to take the
+    /// n-th parent.
+    pub fn resolve_revision(&mut self, revision: &str) -> Result<String, VcsError> {
+        let unknown = || VcsError::UnknownRevision(revision.to_string());
+
+        if let Some(index) = revision.find(['~', '^']) {
+            let mut commit = self.resolve_revision(&revision[..index])?;
+            let mut chars = revision[index..].chars().peekable();
+            while let Some(operator) = chars.next() {
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(|x| x.is_ascii_digit()) {
+                    digits.push(digit);
+                }
+                let count: usize = if digits.is_empty() {
+                    1
+                } else {
+                    digits.parse().map_err(|_| unknown())?
+                };
+
+                match operator {
+                    '~' => {
+                        for _ in 0..count {
+                            commit = self.get_parent(&commit, 0)?.ok_or_else(unknown)?;
+                        }
+                    }
+                    '^' => {
+                        if count > 0 {
+                            commit = self.get_parent(&commit, count - 1)?.ok_or_else(unknown)?;
+                        }
+                    }
+                    _ => return Err(unknown()),
+                }
+            }
+            return Ok(commit);
+        }
+
+        if let Some((name, position)) = revision.strip_suffix('}').and_then(|x| x.split_once("@{"))
+        {
+            let position: usize = position.parse().map_err(|_| unknown())?;
+            if name == "stash" {
+                let stash_list = self.get_stash_list()?;
+                let stash_data = stash_list.stashes.get(position).ok_or_else(unknown)?;
+                return Ok(stash_data.commit.clone());
+            }
+            let branch = match name {
+                "" | "HEAD" => None,
+                branch => Some(branch),
+            };
+            let reflog = self.get_reflog(branch)?;
+            let entry = reflog.iter().rev().nth(position).ok_or_else(unknown)?;
+            if entry.new.is_empty() {
+                return Err(unknown());
+            }
+            return Ok(entry.new.clone());
+        }
+
+        if revision == "HEAD" {
+            return self.get_commit();
+        }
+        if self.get_commit_data(revision)?.is_some() {
+            return Ok(revision.to_string());
+        }
+        if let Some(commits) = self.get_commits(revision)? {
+            if let Some(commit) = commits.last() {
+                return Ok(commit.clone());
+            }
+        }
+
+        if revision.len() >= 4 && revision.chars().all(|x| x.is_ascii_hexdigit()) {
+            let mut matching = self
+                .get_commit_list()?
+                .commits
+                .into_iter()
+                .filter(|x| x.hash.starts_with(revision));
+            if let (Some(commit_data), None) = (matching.next(), matching.next()) {
+                return Ok(commit_data.hash);
+            }
+        }
+        Err(unknown())
+    }
+
+    /// Get the commit and all commits reachable from it through parents
+    pub fn get_ancestors(&mut self, commit: &str) -> Result<HashSet<String>, VcsError> {
+        let mut ancestors: HashSet<String> = HashSet::new();
+        let mut stack = vec![commit.to_string()];
+        while let Some(commit) = stack.pop() {
+            if ancestors.contains(&commit) {
+                continue;
+            }
+            let commit_data = self
+                .get_commit_data(&commit)?
+                .ok_or_else(|| VcsError::UnknownRevision(commit.clone()))?;
+            stack.extend(commit_data.parents);
+            ancestors.insert(commit);
+        }
+        Ok(ancestors)
+    }
+
+    /// Get the n-th parent of the commit, counting from zero
+    pub fn get_parent(&mut self, commit: &str, n: usize) -> Result<Option<String>, VcsError> {
+        match self.get_commit_data(commit)? {
+            Some(commit_data) => Ok(commit_data.parents.get(n).cloned()),
+            None => Err(VcsError::UnknownRevision(commit.to_string())),
+        }
+    }
+
+    /// Initialize repository in the given path, all its hashes use the given algorithm
+    pub fn init_repository(&mut self, hash: HashAlgorithm) -> Result<String, VcsError> {
         let working_dir = self.repo_dir.join(".vcs");
         fs::create_dir(&working_dir)?;
-        fs::create_dir(&working_dir.join("commits"))?;
-
-        let commit = self.commit(&String::from("Initial commit"), &String::from("master"))?;
+        fs::create_dir(working_dir.join("commits"))?;
+        set_format(
+            &self.repo_dir,
+            FormatData {
+                version: FORMAT_VERSION,
+                hash,
+            },
+        )?;
+
+        let commit = self.commit("Initial commit", "master")?;
         Ok(commit)
     }
 
+    /// Get the algorithm of the hashes in the repository
+    pub fn get_hash_algorithm(&self) -> Result<HashAlgorithm, VcsError> {
+        Ok(get_format(&self.repo_dir)?.hash)
+    }
+
     /// Get files and folders of the given commit
-    pub fn get_commit_contents(
-        self: &mut Self,
-        commit: &String,
-    ) -> Result<Vec<PathBuf>, std::io::Error> {
-        get_contents(
-            &self.repo_dir.join(".vcs").join("commits").join(commit),
-            false,
-        )
+    pub fn get_commit_contents(&mut self, commit: &str) -> Result<Vec<PathBuf>, VcsError> {
+        get_contents(&self.get_commit_dir(commit), false)
     }
 
     /// Get commit folder
-    pub fn get_commit_dir(self: &mut Self, commit: &String) -> PathBuf {
+    pub fn get_commit_dir(&self, commit: &str) -> PathBuf {
         self.repo_dir.join(".vcs").join("commits").join(commit)
     }
 
     /// Get file changes in the repository relative to the given commit
     pub fn get_file_changes_commit(
-        self: &mut Self,
-        commit: &String,
-    ) -> Result<Vec<(FileChange, PathBuf)>, std::io::Error> {
+        &mut self,
+        commit: &str,
+    ) -> Result<Vec<(FileChange, PathBuf)>, VcsError> {
         let contents = get_contents(&self.repo_dir, true)?;
         let commit_contents = self.get_commit_contents(commit)?;
-        let commit_dir = self.repo_dir.join(".vcs").join("commits").join(commit);
-        get_file_changes(&self.repo_dir, &contents, &commit_dir, &commit_contents)
+        let commit_dir = self.get_commit_dir(commit);
+        get_file_changes(
+            self.get_hash_algorithm()?,
+            &self.repo_dir,
+            &contents,
+            &commit_dir,
+            &commit_contents,
+        )
     }
 
-    /// Commit the given files and folders
-    pub fn commit_contents(
-        self: &mut Self,
-        message: &String,
-        branch: &String,
-        contents: &Vec<PathBuf>,
-    ) -> Result<String, std::io::Error> {
-        let commit = get_contents_hash(&contents)?;
-
-        copy_files_to_commit(&self.repo_dir, &contents, &commit)?;
-        self.set_commit(&commit)?;
-        self.set_branch(&branch)?;
+    /// Save the given files and folders as a new commit without moving HEAD or any branch
+    pub fn create_commit(
+        &mut self,
+        message: &str,
+        author: &str,
+        contents: &[PathBuf],
+        parents: Vec<String>,
+    ) -> Result<String, VcsError> {
+        let tree = get_contents_hash(&self.repo_dir, contents)?;
+        // Dates are stored in whole milliseconds, the hash has to use the stored date
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_millis() as u64)
+            .unwrap_or(0);
+        let date = UNIX_EPOCH + Duration::from_millis(millis);
+        let hash_algorithm = self.get_hash_algorithm()?;
+        let commit = get_commit_hash(hash_algorithm, &tree, &parents, message, author, date);
+
+        copy_files_to_commit(&self.repo_dir, contents, &commit)?;
         self.add_commit_data(CommitData::from(
             commit.clone(),
-            message.clone(),
-            SystemTime::now(),
+            tree,
+            parents,
+            message.to_string(),
+            author.to_string(),
+            date,
         ))?;
-        self.add_branch_commit(&branch, &commit)?;
+        Ok(commit)
+    }
+
+    /// Commit the given files and folders
+    pub fn commit_contents(
+        &mut self,
+        message: &str,
+        author: &str,
+        branch: &str,
+        contents: &[PathBuf],
+        parents: Vec<String>,
+    ) -> Result<String, VcsError> {
+        let reflog_message = if parents.is_empty() {
+            format!("commit (initial): {}", message)
+        } else {
+            format!("commit: {}", message)
+        };
+        let commit = self.create_commit(message, author, contents, parents)?;
+
+        self.set_commit(&commit, &reflog_message)?;
+        self.set_branch(branch)?;
+        self.add_branch_commit(branch, &commit, &reflog_message)?;
         Ok(commit)
     }
 
     /// Commit from the repo folder
-    pub fn commit(
-        self: &mut Self,
-        message: &String,
-        branch: &String,
-    ) -> Result<String, std::io::Error> {
+    pub fn commit(&mut self, message: &str, branch: &str) -> Result<String, VcsError> {
         let contents = get_contents(&self.repo_dir, true)?;
-        self.commit_contents(message, branch, &contents)
+        let cur_commit = self.get_commit()?;
+        let parents = if cur_commit.is_empty() {
+            Vec::new()
+        } else {
+            vec![cur_commit]
+        };
+        self.commit_contents(message, &get_author(), branch, &contents, parents)
     }
 
     /// Get a branch containing the commit
     pub fn get_branch_with_commit(
-        self: &mut Self,
-        commit: &String,
-        branch_priority: Option<&String>
-    ) -> Result<Option<String>, std::io::Error> {
+        &mut self,
+        commit: &str,
+        branch_priority: Option<&str>,
+    ) -> Result<Option<String>, VcsError> {
         let branch_list = self.get_branch_list()?;
 
         let mut res_branch: Option<String> = None;
         for branch in branch_list.branches {
-            if branch.commits.iter().find(|x| *x == commit).is_some() {
-                if branch_priority.is_some() && branch.name == branch_priority.unwrap().clone() {
+            if branch.commits.iter().any(|x| x == commit) {
+                if branch_priority == Some(branch.name.as_str()) {
                     return Ok(Some(branch.name));
                 }
                 res_branch = Some(branch.name);
@@ -260,31 +590,58 @@ impl VcsStateManager {
         Ok(res_branch)
     }
 
-    // Replace repo contents with the contents of the given commit
-    pub fn jump_to_commit(self: &mut Self, commit: &String, branch_priority: Option<&String>) -> Result<(), std::io::Error> {
+    /// Replace repo contents with the contents of the given commit
+    ///
+    /// If no branch contains the commit, the current branch is kept.
+    pub fn jump_to_commit(
+        &mut self,
+        commit: &str,
+        branch_priority: Option<&str>,
+    ) -> Result<(), VcsError> {
         remove_repo_files(&self.repo_dir)?;
-
-        let branch_with_commit = self.get_branch_with_commit(commit, branch_priority)?.unwrap();
-        self.set_commit(commit)?;
-        self.set_branch(&branch_with_commit)?;
+        self.move_head(commit, branch_priority)?;
         copy_files_from_commit(&self.repo_dir, commit)
     }
 
+    /// Move HEAD to the given commit without changing repo contents
+    ///
+    /// If no branch contains the commit, the current branch is kept.
+    pub fn move_head(
+        &mut self,
+        commit: &str,
+        branch_priority: Option<&str>,
+    ) -> Result<(), VcsError> {
+        let branch_with_commit = match self.get_branch_with_commit(commit, branch_priority)? {
+            Some(branch) => branch,
+            None => self.get_branch()?,
+        };
+        self.set_commit(
+            commit,
+            &format!("jump: moving to {} on {}", commit, branch_with_commit),
+        )?;
+        self.set_branch(&branch_with_commit)
+    }
+
     /// Create a new branch from the given commit
-    pub fn new_branch(self: &mut Self, new_branch: &String) -> Result<(), std::io::Error> {
+    pub fn new_branch(&mut self, new_branch: &str) -> Result<(), VcsError> {
         let cur_commit = self.get_commit()?;
-        self.add_branch_commit(new_branch, &cur_commit)
+        let cur_branch = self.get_branch()?;
+        self.add_branch_commit(
+            new_branch,
+            &cur_commit,
+            &format!("branch: created from {}", cur_branch),
+        )
     }
 
     /// Get commits history
-    pub fn get_commit_history(self: &mut Self) -> Result<Vec<String>, std::io::Error> {
+    pub fn get_commit_history(&mut self) -> Result<Vec<String>, VcsError> {
         let cur_commit = self.get_commit()?;
         let cur_branch = self.get_branch()?;
 
         let mut commit_history: Vec<String> = Vec::new();
-        if cur_branch != String::from("master") {
-            let cur_branch_commits = self.get_commits(&cur_branch)?.unwrap();
-            for commit in self.get_commits(&String::from("master"))?.unwrap() {
+        if cur_branch != "master" {
+            let cur_branch_commits = self.get_existing_commits(&cur_branch)?;
+            for commit in self.get_existing_commits("master")? {
                 if commit == cur_branch_commits[0] {
                     break;
                 }
@@ -292,7 +649,7 @@ impl VcsStateManager {
             }
         }
 
-        for commit in self.get_commits(&cur_branch)?.unwrap() {
+        for commit in self.get_existing_commits(&cur_branch)? {
             commit_history.push(commit.clone());
             if commit == cur_commit {
                 break;
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_borrow, clippy::needless_borrows_for_generic_args)]
+
 use std::{
     fs::{self, File},
     path::{Path, PathBuf},
@@ -5,15 +7,52 @@ use std::{
 
 use vcs::{
     commands::{
-        commit::commit_in_repo,
-        init::init,
-        jump::{jump_branch_in_repo, jump_commit_in_repo, JumpResult},
-        log::log_in_repo,
+        bisect::{
+            bisect_mark_in_repo, bisect_reset_in_repo, bisect_run_in_repo, bisect_start_in_repo,
+            BisectMark, BisectResult,
+        },
+        blame::{blame_in_repo, BlameResult},
+        cherry_pick::{
+            cherry_pick_abort_in_repo, cherry_pick_continue_in_repo, cherry_pick_in_repo,
+            CherryPickResult,
+        },
+        commit::{amend_in_repo, commit_in_repo},
+        fsck::{fsck_in_repo, FsckProblem},
+        grep::{grep_in_repo, GrepMatch, GrepOptions},
+        init::{init, init_with_hash},
+        jump::{
+            jump_branch_in_repo, jump_branch_with_mode_in_repo, jump_commit_in_repo,
+            jump_commit_with_mode_in_repo, JumpMode, JumpResult,
+        },
+        lfs::{
+            lfs_list_in_repo, lfs_prune_in_repo, lfs_threshold_in_repo, lfs_track_in_repo,
+            lfs_untrack_in_repo, LfsResult,
+        },
+        log::{format_commit, log_in_repo, log_with_options_in_repo, parse_date, LogOptions},
         merge::{merge_in_repo, MergeResult},
-        new_branch::{new_branch_in_repo, NewBranchResult},
+        new_branch::{new_branch_in_repo, restore_branch_in_repo, NewBranchResult},
+        rebase::{rebase_abort_in_repo, rebase_continue_in_repo, rebase_in_repo, RebaseResult},
+        reflog::reflog_in_repo,
+        reset::{reset_in_repo, ResetMode},
+        restore::restore_in_repo,
+        revert::{revert_in_repo, RevertResult},
+        show::{show_in_repo, FileDiff, ShowResult},
+        stash::{
+            stash_apply_in_repo, stash_drop_in_repo, stash_list_in_repo, stash_push_in_repo,
+            stash_show_in_repo, StashResult,
+        },
         status::{status_in_repo, StatusResult},
+        upgrade::{upgrade_in_repo, UpgradeResult},
+    },
+    error::VcsError,
+    graph::{draw_graph, GraphLine},
+    hash::HashAlgorithm,
+    json_files::{
+        get_branch_list, get_commit_list, get_format, get_stash_list, set_commit_list,
+        LargeFilesConfig,
     },
-    repo_file_manager::FileChange,
+    large_objects::Pointer,
+    repo_file_manager::{escape_path, FileChange, SIMILARITY_MAX_LINES},
 };
 
 fn modify_file(path: &PathBuf, content: &String) -> Result<(), std::io::Error> {
@@ -218,3 +257,1989 @@ fn it_works() {
 
     fs::remove_dir_all(&repo_dir).unwrap();
 }
+
+#[test]
+fn errors_are_typed() {
+    let repo_dir = Path::new(".").join("test_repo5");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    assert!(matches!(
+        status_in_repo(repo_dir.clone()),
+        Err(VcsError::NotARepository)
+    ));
+
+    init(repo_dir.clone()).unwrap();
+
+    assert!(matches!(
+        merge_in_repo(repo_dir.clone(), "missing_branch"),
+        Err(VcsError::BranchNotFound(branch)) if branch == "missing_branch"
+    ));
+
+    fs::write(repo_dir.join(".vcs").join("branch_list.json"), "{").unwrap();
+    assert!(matches!(
+        commit_in_repo(repo_dir.clone(), "message"),
+        Err(VcsError::CorruptMetadata { file }) if file == repo_dir.join(".vcs").join("branch_list.json")
+    ));
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+/// Check that exactly the expected problems have been found in any order
+fn assert_problems(problems: &[(FsckProblem, bool)], expected: &[(FsckProblem, bool)]) {
+    for problem in expected.iter() {
+        assert!(problems.contains(problem), "{:?} not found", problem);
+    }
+    assert_eq!(problems.len(), expected.len());
+}
+
+#[test]
+fn fsck_finds_and_repairs_problems() {
+    let repo_dir = Path::new(".").join("test_repo6");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let first_commit = init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("content")).unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "message2").unwrap().commit;
+    modify_file(&repo_dir.join("file"), &String::from("other content")).unwrap();
+    let third_commit = commit_in_repo(repo_dir.clone(), "message3").unwrap().commit;
+    modify_file(&repo_dir.join("file"), &String::from("last content")).unwrap();
+    let fourth_commit = commit_in_repo(repo_dir.clone(), "message4").unwrap().commit;
+    modify_file(&repo_dir.join("file"), &String::from("stashed")).unwrap();
+    stash_push_in_repo(repo_dir.clone(), None).unwrap();
+    let stash_commit = get_stash_list(&repo_dir).unwrap().stashes[0].commit.clone();
+
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+
+    let commits_dir = repo_dir.join(".vcs").join("commits");
+    fs::write(commits_dir.join(&second_commit).join("file"), "changed").unwrap();
+    fs::remove_dir_all(commits_dir.join(&third_commit)).unwrap();
+    fs::remove_dir_all(commits_dir.join(&stash_commit)).unwrap();
+    fs::create_dir(commits_dir.join("dangling")).unwrap();
+    let mut commit_list = get_commit_list(&repo_dir).unwrap();
+    for commit_data in commit_list.commits.iter_mut() {
+        if commit_data.hash == fourth_commit {
+            commit_data.message = String::from("tampered");
+        }
+    }
+    set_commit_list(&repo_dir, commit_list).unwrap();
+
+    let problems = fsck_in_repo(repo_dir.clone(), true).unwrap().problems;
+    // The problems which can not be repaired go first
+    let expected = [
+        (
+            FsckProblem::CorruptSnapshot {
+                commit: second_commit.clone(),
+            },
+            false,
+        ),
+        (
+            FsckProblem::CorruptCommit {
+                commit: fourth_commit.clone(),
+            },
+            false,
+        ),
+        (
+            FsckProblem::DanglingSnapshot {
+                commit: String::from("dangling"),
+            },
+            false,
+        ),
+        (
+            FsckProblem::InvalidParent {
+                commit: fourth_commit.clone(),
+                parent: third_commit.clone(),
+            },
+            false,
+        ),
+        (
+            FsckProblem::MissingSnapshot {
+                commit: third_commit.clone(),
+            },
+            true,
+        ),
+        (
+            FsckProblem::MissingSnapshot {
+                commit: stash_commit.clone(),
+            },
+            true,
+        ),
+        (
+            FsckProblem::MissingCommit {
+                branch: String::from("master"),
+                commit: third_commit.clone(),
+            },
+            true,
+        ),
+        (
+            FsckProblem::InvalidStashCommit {
+                commit: stash_commit,
+            },
+            true,
+        ),
+    ];
+    assert_problems(&problems, &expected);
+
+    // The branch is cut at the missing commit instead of skipping it
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    assert_eq!(
+        branch_list.branches[0].commits,
+        vec![first_commit, second_commit.clone()]
+    );
+    assert!(get_stash_list(&repo_dir).unwrap().stashes.is_empty());
+
+    let problems = fsck_in_repo(repo_dir.clone(), false).unwrap().problems;
+    assert_problems(&problems, &expected[..4]);
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn upgrade_from_version_1() {
+    let repo_dir = Path::new(".").join("test_repo7");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let first_commit = init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("content")).unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "message").unwrap().commit;
+
+    fs::remove_file(repo_dir.join(".vcs").join("format.json")).unwrap();
+    let mut commit_list = get_commit_list(&repo_dir).unwrap();
+    for commit_data in commit_list.commits.iter_mut() {
+        commit_data.parents.clear();
+    }
+    set_commit_list(&repo_dir, commit_list).unwrap();
+
+    // Commits of version 1 have ids of an older hash scheme
+    let old_commit = "0".repeat(40);
+    let vcs_dir = repo_dir.join(".vcs");
+    for path in [
+        vcs_dir.join("commit_list.json"),
+        vcs_dir.join("branch_list.json"),
+        vcs_dir.join("state.json"),
+        vcs_dir.join("logs").join("HEAD"),
+        vcs_dir.join("logs").join("branches").join("master"),
+    ] {
+        let data = fs::read_to_string(&path).unwrap();
+        fs::write(&path, data.replace(&second_commit, &old_commit)).unwrap();
+    }
+    fs::rename(
+        vcs_dir.join("commits").join(&second_commit),
+        vcs_dir.join("commits").join(&old_commit),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        status_in_repo(repo_dir.clone()),
+        Err(VcsError::UnsupportedFormat { version: 1 })
+    ));
+    // Snapshots of older formats are hashed differently, so they are not checked
+    assert!(matches!(
+        fsck_in_repo(repo_dir.clone(), false),
+        Err(VcsError::UnsupportedFormat { version: 1 })
+    ));
+
+    match upgrade_in_repo(repo_dir.clone()).unwrap() {
+        UpgradeResult::Upgraded { from, to, backup } => {
+            assert_eq!(from, 1);
+            assert_eq!(to, 6);
+            assert!(backup.join("commit_list.json").exists());
+            assert!(backup.join("commits").join(&old_commit).is_dir());
+        }
+        _ => panic!("Expected the repository to be upgraded"),
+    }
+    assert_eq!(
+        upgrade_in_repo(repo_dir.clone()).unwrap(),
+        UpgradeResult::UpToDate { version: 6 }
+    );
+
+    let commit_list = get_commit_list(&repo_dir).unwrap();
+    let second_commit_data = commit_list
+        .commits
+        .iter()
+        .find(|x| x.hash == second_commit)
+        .unwrap();
+    assert_eq!(second_commit_data.parents, vec![first_commit.clone()]);
+    assert!(!vcs_dir.join("commits").join(&old_commit).exists());
+    assert_eq!(
+        get_branch_list(&repo_dir).unwrap().branches[0].commits,
+        vec![first_commit, second_commit.clone()]
+    );
+    assert_eq!(
+        reflog_in_repo(repo_dir.clone(), None).unwrap().entries[0].new,
+        second_commit
+    );
+    assert!(status_in_repo(repo_dir.clone()).is_ok());
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn reflog_records_movements() {
+    let repo_dir = Path::new(".").join("test_repo8");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let first_commit = init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("content")).unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "message").unwrap().commit;
+
+    let entries = reflog_in_repo(repo_dir.clone(), None).unwrap().entries;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].old, first_commit);
+    assert_eq!(entries[0].new, second_commit);
+    assert_eq!(entries[0].message, "commit: message");
+    assert_eq!(entries[1].message, "commit (initial): Initial commit");
+
+    assert_eq!(
+        jump_commit_in_repo(repo_dir.clone(), "@{1}", None).unwrap(),
+        JumpResult::Success {
+            commit: first_commit.clone(),
+            branch: String::from("master")
+        }
+    );
+    assert_eq!(
+        jump_commit_in_repo(repo_dir.clone(), "HEAD@{1}", None).unwrap(),
+        JumpResult::Success {
+            commit: second_commit.clone(),
+            branch: String::from("master")
+        }
+    );
+    assert_eq!(
+        jump_commit_in_repo(repo_dir.clone(), "@{10}", None).unwrap(),
+        JumpResult::NotFound
+    );
+
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(&repo_dir.join("feature_file"), &String::from("feature")).unwrap();
+    let feature_commit = commit_in_repo(repo_dir.clone(), "feature").unwrap().commit;
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    merge_in_repo(repo_dir.clone(), "feature").unwrap();
+
+    let entries = reflog_in_repo(repo_dir.clone(), Some("feature"))
+        .unwrap()
+        .entries;
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].old, feature_commit);
+    assert_eq!(entries[0].new, "");
+    assert_eq!(entries[2].new, second_commit);
+    assert!(matches!(
+        reflog_in_repo(repo_dir.clone(), Some("missing")),
+        Err(VcsError::BranchNotFound(_))
+    ));
+
+    // Branch names become reflog file names, so they can not leave .vcs/logs
+    for name in ["../../escaped", "a/b", "..", ""] {
+        assert!(matches!(
+            new_branch_in_repo(repo_dir.clone(), name),
+            Err(VcsError::InvalidBranchName(_))
+        ));
+        assert!(matches!(
+            restore_branch_in_repo(repo_dir.clone(), name),
+            Err(VcsError::InvalidBranchName(_))
+        ));
+    }
+    assert!(!repo_dir.join(".vcs").join("escaped").exists());
+    assert!(!repo_dir.join("escaped").exists());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn restore_merged_branch() {
+    let repo_dir = Path::new(".").join("test_repo9");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let first_commit = init(repo_dir.clone()).unwrap();
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    let mut feature_commits = vec![first_commit];
+    for i in 0..3 {
+        modify_file(&repo_dir.join("feature_file"), &i.to_string()).unwrap();
+        feature_commits.push(commit_in_repo(repo_dir.clone(), "feature").unwrap().commit);
+    }
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    merge_in_repo(repo_dir.clone(), "feature").unwrap();
+
+    assert_eq!(
+        restore_branch_in_repo(repo_dir.clone(), "missing").unwrap(),
+        NewBranchResult::NotFound
+    );
+    assert_eq!(
+        restore_branch_in_repo(repo_dir.clone(), "feature").unwrap(),
+        NewBranchResult::Restored {
+            commit: feature_commits.last().unwrap().clone()
+        }
+    );
+    assert_eq!(
+        restore_branch_in_repo(repo_dir.clone(), "feature").unwrap(),
+        NewBranchResult::AlreadyExists
+    );
+
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    let feature = branch_list
+        .branches
+        .iter()
+        .find(|x| x.name == "feature")
+        .unwrap();
+    assert_eq!(feature.commits, feature_commits);
+    assert_eq!(
+        jump_branch_in_repo(repo_dir.clone(), "feature").unwrap(),
+        JumpResult::Success {
+            commit: feature_commits.last().unwrap().clone(),
+            branch: String::from("feature")
+        }
+    );
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("feature_file")).unwrap(),
+        "2"
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn restore_rewritten_branch() {
+    let repo_dir = Path::new(".").join("test_repo30");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(&repo_dir.join("a"), &String::from("1")).unwrap();
+    let first_feature = commit_in_repo(repo_dir.clone(), "feature 1")
+        .unwrap()
+        .commit;
+    modify_file(&repo_dir.join("a"), &String::from("2")).unwrap();
+    commit_in_repo(repo_dir.clone(), "feature 2").unwrap();
+    amend_in_repo(repo_dir.clone(), Some("feature 2 amended")).unwrap();
+    reset_in_repo(repo_dir.clone(), &first_feature, ResetMode::Hard).unwrap();
+    modify_file(&repo_dir.join("b"), &String::from("b")).unwrap();
+    commit_in_repo(repo_dir.clone(), "feature 3").unwrap();
+
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    modify_file(&repo_dir.join("m"), &String::from("m")).unwrap();
+    let master_commit = commit_in_repo(repo_dir.clone(), "master").unwrap().commit;
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    let rebased = match rebase_in_repo(repo_dir.clone(), "master", None).unwrap() {
+        RebaseResult::Success { commits, .. } => commits,
+        result => panic!("unexpected result {:?}", result),
+    };
+    assert_eq!(rebased.len(), 2);
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    merge_in_repo(repo_dir.clone(), "feature").unwrap();
+
+    // The branch is rebuilt from the last tip, not from every tip it ever had
+    assert_eq!(
+        restore_branch_in_repo(repo_dir.clone(), "feature").unwrap(),
+        NewBranchResult::Restored {
+            commit: rebased[1].clone()
+        }
+    );
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    let feature = branch_list
+        .branches
+        .iter()
+        .find(|x| x.name == "feature")
+        .unwrap();
+    assert_eq!(
+        feature.commits,
+        vec![master_commit, rebased[0].clone(), rebased[1].clone()]
+    );
+    let commit_list = get_commit_list(&repo_dir).unwrap();
+    for pair in feature.commits.windows(2) {
+        let commit_data = commit_list
+            .commits
+            .iter()
+            .find(|x| x.hash == pair[1])
+            .unwrap();
+        assert_eq!(commit_data.parents[0], pair[0]);
+    }
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn show_commits_and_files() {
+    let repo_dir = Path::new(".").join("test_repo10");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    fs::create_dir(repo_dir.join("dir")).unwrap();
+    modify_file(
+        &repo_dir.join("dir").join("file"),
+        &String::from("a\nb\nc\n"),
+    )
+    .unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "first").unwrap().commit;
+    modify_file(
+        &repo_dir.join("dir").join("file"),
+        &String::from("a\nB\nc\n"),
+    )
+    .unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "second").unwrap().commit;
+
+    match show_in_repo(repo_dir.clone(), "HEAD").unwrap() {
+        ShowResult::Commit {
+            commit_data,
+            file_diffs,
+        } => {
+            assert_eq!(commit_data.hash, second_commit);
+            assert_eq!(commit_data.parents, vec![first_commit.clone()]);
+            assert_eq!(
+                file_diffs,
+                vec![FileDiff {
+                    change: FileChange::Modified,
+                    path: Path::new("dir").join("file"),
+                    binary: false,
+                    lines: vec![
+                        String::from("@@ -1,3 +1,3 @@"),
+                        String::from(" a"),
+                        String::from("-b"),
+                        String::from("+B"),
+                        String::from(" c"),
+                    ]
+                }]
+            );
+        }
+        _ => panic!("expected a commit"),
+    }
+
+    match show_in_repo(repo_dir.clone(), "HEAD~1:dir/file").unwrap() {
+        ShowResult::File { contents } => assert_eq!(contents, b"a\nb\nc\n"),
+        _ => panic!("expected a file"),
+    }
+    match show_in_repo(repo_dir.clone(), &format!("{}:dir", &second_commit[..8])).unwrap() {
+        ShowResult::Directory { entries } => {
+            assert_eq!(entries, vec![Path::new("dir").join("file")])
+        }
+        _ => panic!("expected a directory"),
+    }
+    assert!(matches!(
+        show_in_repo(repo_dir.clone(), "HEAD:missing"),
+        Err(VcsError::PathNotFound { .. })
+    ));
+    assert!(matches!(
+        show_in_repo(repo_dir.clone(), "HEAD:../state.json"),
+        Err(VcsError::PathNotFound { .. })
+    ));
+    assert!(matches!(
+        show_in_repo(repo_dir.clone(), "HEAD~5"),
+        Err(VcsError::UnknownRevision(_))
+    ));
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn restore_paths_from_commit() {
+    let repo_dir = Path::new(".").join("test_repo11");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    fs::create_dir(repo_dir.join("dir")).unwrap();
+    modify_file(&repo_dir.join("dir").join("file1"), &String::from("1")).unwrap();
+    modify_file(&repo_dir.join("dir").join("file2"), &String::from("2")).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("old")).unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "first").unwrap().commit;
+    modify_file(&repo_dir.join("file"), &String::from("new")).unwrap();
+    fs::remove_file(repo_dir.join("dir").join("file2")).unwrap();
+    commit_in_repo(repo_dir.clone(), "second").unwrap();
+
+    modify_file(&repo_dir.join("dir").join("file1"), &String::from("local")).unwrap();
+    modify_file(&repo_dir.join("dir").join("file3"), &String::from("3")).unwrap();
+    let restore_result =
+        restore_in_repo(repo_dir.clone(), "HEAD", &[Path::new("dir").to_path_buf()]).unwrap();
+    assert_eq!(
+        restore_result.file_changes,
+        vec![
+            (FileChange::Modified, repo_dir.join("dir").join("file1")),
+            (FileChange::Removed, repo_dir.join("dir").join("file3")),
+        ]
+    );
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    let restore_result = restore_in_repo(
+        repo_dir.clone(),
+        &first_commit,
+        &[
+            Path::new("file").to_path_buf(),
+            Path::new("dir").join("file2"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(restore_result.commit, first_commit);
+    assert_eq!(
+        restore_result.file_changes,
+        vec![
+            (FileChange::Added, repo_dir.join("dir").join("file2")),
+            (FileChange::Modified, repo_dir.join("file")),
+        ]
+    );
+    assert_eq!(fs::read_to_string(repo_dir.join("file")).unwrap(), "old");
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("dir").join("file2")).unwrap(),
+        "2"
+    );
+
+    assert!(matches!(
+        restore_in_repo(repo_dir.clone(), "HEAD", &[Path::new("dir").join("file2")]),
+        Err(VcsError::PathNotFound { .. })
+    ));
+    assert!(matches!(
+        restore_in_repo(repo_dir.clone(), "HEAD", &[Path::new("..").to_path_buf()]),
+        Err(VcsError::PathNotFound { .. })
+    ));
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn reset_moves_branch_tip() {
+    let repo_dir = Path::new(".").join("test_repo12");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let mut commits = vec![init(repo_dir.clone()).unwrap()];
+    for i in 0..3 {
+        modify_file(&repo_dir.join("file"), &i.to_string()).unwrap();
+        commits.push(commit_in_repo(repo_dir.clone(), "commit").unwrap().commit);
+    }
+
+    let reset_result = reset_in_repo(repo_dir.clone(), "HEAD~2", ResetMode::Mixed).unwrap();
+    assert_eq!(reset_result.old_commit, commits[3]);
+    assert_eq!(reset_result.commit, commits[1]);
+    assert_eq!(
+        reset_result.file_changes,
+        vec![(FileChange::Modified, repo_dir.join("file"))]
+    );
+    assert_eq!(fs::read_to_string(repo_dir.join("file")).unwrap(), "2");
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    assert_eq!(branch_list.branches[0].commits, commits[..2]);
+    let reflog_result = reflog_in_repo(repo_dir.clone(), Some("master")).unwrap();
+    assert_eq!(reflog_result.entries[0].old, commits[3]);
+    assert_eq!(reflog_result.entries[0].new, commits[1]);
+
+    let reset_result = reset_in_repo(repo_dir.clone(), "master@{1}", ResetMode::Hard).unwrap();
+    assert_eq!(reset_result.commit, commits[3]);
+    assert!(reset_result.file_changes.is_empty());
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    assert_eq!(branch_list.branches[0].commits, commits);
+
+    reset_in_repo(repo_dir.clone(), &commits[2], ResetMode::Hard).unwrap();
+    assert_eq!(fs::read_to_string(repo_dir.join("file")).unwrap(), "1");
+    modify_file(&repo_dir.join("file"), &String::from("new")).unwrap();
+    let commit_result = commit_in_repo(repo_dir.clone(), "new").unwrap();
+    assert!(commit_result.successful);
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    assert_eq!(
+        branch_list.branches[0].commits,
+        vec![
+            commits[0].clone(),
+            commits[1].clone(),
+            commits[2].clone(),
+            commit_result.commit
+        ]
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn revert_commits() {
+    let repo_dir = Path::new(".").join("test_repo13");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let file = repo_dir.join("file");
+    modify_file(&file, &String::from("a\nb\nc\nd\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "first").unwrap();
+    modify_file(&file, &String::from("a\nB\nc\nd\ne\n")).unwrap();
+    modify_file(&repo_dir.join("added"), &String::from("added")).unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "second").unwrap().commit;
+    modify_file(&file, &String::from("a\nB\nc\nD\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "third").unwrap();
+
+    modify_file(&file, &String::from("local")).unwrap();
+    assert!(matches!(
+        revert_in_repo(repo_dir.clone(), &second_commit).unwrap(),
+        RevertResult::UncommitedChanges { .. }
+    ));
+    modify_file(&file, &String::from("a\nB\nc\nD\ne\n")).unwrap();
+
+    match revert_in_repo(repo_dir.clone(), &second_commit).unwrap() {
+        RevertResult::Success {
+            commit,
+            file_changes,
+        } => {
+            assert_eq!(
+                file_changes,
+                vec![
+                    (FileChange::Modified, file.clone()),
+                    (FileChange::Removed, repo_dir.join("added")),
+                ]
+            );
+            let commit_list = get_commit_list(&repo_dir).unwrap();
+            let commit_data = commit_list
+                .commits
+                .iter()
+                .find(|x| x.hash == commit)
+                .unwrap();
+            assert!(commit_data.message.starts_with("Revert \"second\""));
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nb\nc\nD\ne\n");
+    assert!(!repo_dir.join("added").exists());
+    assert_eq!(
+        revert_in_repo(repo_dir.clone(), &second_commit).unwrap(),
+        RevertResult::NothingToRevert
+    );
+
+    modify_file(&file, &String::from("a\nX\nc\nD\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "fourth").unwrap();
+    reset_in_repo(repo_dir.clone(), "HEAD", ResetMode::Hard).unwrap();
+    match revert_in_repo(repo_dir.clone(), "HEAD~3").unwrap() {
+        RevertResult::MergeConflict { paths, .. } => {
+            assert_eq!(paths, vec![PathBuf::from("file")]);
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "a\n<<<<<<< HEAD\nX\n=======\nb\n>>>>>>> parent of ".to_string()
+            + &get_commit_list(&repo_dir).unwrap().commits[2].hash
+            + "\nc\nD\ne\n"
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn cherry_pick_with_conflicts() {
+    let repo_dir = Path::new(".").join("test_repo14");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let file = repo_dir.join("file");
+    modify_file(&file, &String::from("a\nb\nc\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "base").unwrap();
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(&repo_dir.join("fix"), &String::from("fix")).unwrap();
+    let fix_commit = commit_in_repo(repo_dir.clone(), "fix").unwrap().commit;
+    modify_file(&file, &String::from("a\nfeature\nc\n")).unwrap();
+    let feature_commit = commit_in_repo(repo_dir.clone(), "feature").unwrap().commit;
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    modify_file(&file, &String::from("a\nmaster\nc\n")).unwrap();
+    let master_commit = commit_in_repo(repo_dir.clone(), "master").unwrap().commit;
+
+    let conflict = cherry_pick_in_repo(
+        repo_dir.clone(),
+        &[fix_commit.clone(), String::from("feature")],
+    )
+    .unwrap();
+    assert_eq!(
+        conflict,
+        CherryPickResult::MergeConflict {
+            commit: feature_commit.clone(),
+            paths: vec![PathBuf::from("file")]
+        }
+    );
+    assert_eq!(fs::read_to_string(repo_dir.join("fix")).unwrap(), "fix");
+    assert_eq!(
+        cherry_pick_in_repo(repo_dir.clone(), &[String::from("feature~1")]).unwrap(),
+        CherryPickResult::InProgress {
+            operation: String::from("cherry-pick")
+        }
+    );
+
+    modify_file(&file, &String::from("a\nresolved\nc\n")).unwrap();
+    let commits = match cherry_pick_continue_in_repo(repo_dir.clone()).unwrap() {
+        CherryPickResult::Success { commits } => commits,
+        result => panic!("unexpected result {:?}", result),
+    };
+    assert_eq!(commits.len(), 1);
+    assert_eq!(
+        cherry_pick_continue_in_repo(repo_dir.clone()).unwrap(),
+        CherryPickResult::NotInProgress
+    );
+
+    let commit_list = get_commit_list(&repo_dir).unwrap();
+    let find = |commit: &String| commit_list.commits.iter().find(|x| x.hash == *commit);
+    let picked = find(&commits[0]).unwrap();
+    let original = find(&feature_commit).unwrap();
+    assert_eq!(picked.message, original.message);
+    assert_eq!(picked.author, original.author);
+    let master = &get_branch_list(&repo_dir).unwrap().branches[0];
+    assert_eq!(master.commits.len(), 5);
+    assert_eq!(master.commits[2], master_commit);
+    assert_eq!(master.commits[3], picked.parents[0]);
+    assert_eq!(master.commits[4], picked.hash);
+
+    assert!(matches!(
+        cherry_pick_in_repo(repo_dir.clone(), &[String::from("feature")]).unwrap(),
+        CherryPickResult::MergeConflict { .. }
+    ));
+    assert_eq!(
+        cherry_pick_abort_in_repo(repo_dir.clone()).unwrap(),
+        CherryPickResult::Aborted {
+            commit: commits[0].clone()
+        }
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nresolved\nc\n");
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn rebase_branches() {
+    let repo_dir = Path::new(".").join("test_repo15");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let file = repo_dir.join("file");
+    modify_file(&file, &String::from("a\nb\nc\nd\ne\n")).unwrap();
+    let base_commit = commit_in_repo(repo_dir.clone(), "base").unwrap().commit;
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    new_branch_in_repo(repo_dir.clone(), "conflict").unwrap();
+    modify_file(&repo_dir.join("master"), &String::from("master")).unwrap();
+    modify_file(&file, &String::from("a\nmaster\nc\nd\ne\n")).unwrap();
+    let master_commit = commit_in_repo(repo_dir.clone(), "master").unwrap().commit;
+
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(&repo_dir.join("feature"), &String::from("feature")).unwrap();
+    commit_in_repo(repo_dir.clone(), "feature 1").unwrap();
+    modify_file(&file, &String::from("a\nb\nc\nfeature\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "feature 2").unwrap();
+
+    let (commit, commits) = match rebase_in_repo(repo_dir.clone(), "master", None).unwrap() {
+        RebaseResult::Success {
+            branch,
+            commit,
+            commits,
+        } => {
+            assert_eq!(branch, "feature");
+            (commit, commits)
+        }
+        result => panic!("unexpected result {:?}", result),
+    };
+    let feature = get_branch_list(&repo_dir)
+        .unwrap()
+        .branches
+        .into_iter()
+        .find(|x| x.name == "feature")
+        .unwrap();
+    assert_eq!(feature.commits[0], master_commit);
+    assert_eq!(feature.commits[1..], commits);
+    assert_eq!(commits.last(), Some(&commit));
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "a\nmaster\nc\nfeature\ne\n"
+    );
+    assert!(repo_dir.join("master").exists());
+
+    rebase_in_repo(repo_dir.clone(), "master", Some(&base_commit)).unwrap();
+    assert!(!repo_dir.join("master").exists());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nb\nc\nfeature\ne\n");
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    assert!(matches!(
+        merge_in_repo(repo_dir.clone(), "feature"),
+        Ok(MergeResult::MergeConflict { .. })
+    ));
+
+    jump_branch_in_repo(repo_dir.clone(), "conflict").unwrap();
+    modify_file(&file, &String::from("a\nconflict\nc\nd\ne\n")).unwrap();
+    let conflict_commit = commit_in_repo(repo_dir.clone(), "conflict").unwrap().commit;
+    assert_eq!(
+        rebase_in_repo(repo_dir.clone(), "master", None).unwrap(),
+        RebaseResult::MergeConflict {
+            commit: conflict_commit.clone(),
+            paths: vec![PathBuf::from("file")]
+        }
+    );
+    assert_eq!(
+        rebase_abort_in_repo(repo_dir.clone()).unwrap(),
+        RebaseResult::Aborted {
+            commit: conflict_commit.clone()
+        }
+    );
+    let conflict = get_branch_list(&repo_dir)
+        .unwrap()
+        .branches
+        .into_iter()
+        .find(|x| x.name == "conflict")
+        .unwrap();
+    assert_eq!(conflict.commits, vec![base_commit, conflict_commit.clone()]);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nconflict\nc\nd\ne\n");
+
+    rebase_in_repo(repo_dir.clone(), "master", None).unwrap();
+    modify_file(&file, &String::from("a\nresolved\nc\nd\ne\n")).unwrap();
+    assert!(matches!(
+        rebase_continue_in_repo(repo_dir.clone()).unwrap(),
+        RebaseResult::Success { .. }
+    ));
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    assert!(matches!(
+        merge_in_repo(repo_dir.clone(), "conflict").unwrap(),
+        MergeResult::Success { .. }
+    ));
+    match show_in_repo(repo_dir.clone(), "master:file").unwrap() {
+        ShowResult::File { contents } => assert_eq!(contents, b"a\nresolved\nc\nd\ne\n"),
+        _ => panic!("expected a file"),
+    }
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn amend_last_commit() {
+    let repo_dir = Path::new(".").join("test_repo16");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let first_commit = init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("file")).unwrap();
+    let typo_commit = commit_in_repo(repo_dir.clone(), "tpyo").unwrap().commit;
+
+    let message_commit = amend_in_repo(repo_dir.clone(), Some("typo")).unwrap();
+    assert!(message_commit.successful);
+    assert!(message_commit.file_changes.is_empty());
+    modify_file(&repo_dir.join("forgotten"), &String::from("forgotten")).unwrap();
+    let amend_result = amend_in_repo(repo_dir.clone(), None).unwrap();
+    assert_eq!(
+        amend_result.file_changes,
+        vec![(FileChange::Added, repo_dir.join("forgotten"))]
+    );
+
+    let branch_list = get_branch_list(&repo_dir).unwrap();
+    assert_eq!(
+        branch_list.branches[0].commits,
+        vec![first_commit.clone(), amend_result.commit.clone()]
+    );
+    let commit_list = get_commit_list(&repo_dir).unwrap();
+    let commit_data = commit_list
+        .commits
+        .iter()
+        .find(|x| x.hash == amend_result.commit)
+        .unwrap();
+    assert_eq!(commit_data.message, "typo");
+    assert_eq!(commit_data.parents, vec![first_commit]);
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    let reflog_result = reflog_in_repo(repo_dir.clone(), Some("master")).unwrap();
+    assert_eq!(reflog_result.entries[0].old, message_commit.commit);
+    assert_eq!(reflog_result.entries[1].old, typo_commit);
+    assert_eq!(reflog_result.entries[0].message, "commit (amend): typo");
+
+    jump_commit_in_repo(repo_dir.clone(), "HEAD~1", None).unwrap();
+    assert!(!amend_in_repo(repo_dir.clone(), None).unwrap().successful);
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn stash_local_changes() {
+    let repo_dir = Path::new(".").join("test_repo17");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let file = repo_dir.join("file");
+    modify_file(&file, &String::from("a\nb\nc\nd\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "base").unwrap();
+
+    assert_eq!(
+        stash_push_in_repo(repo_dir.clone(), None).unwrap(),
+        StashResult::NoLocalChanges
+    );
+    modify_file(&file, &String::from("a\nstash\nc\nd\ne\n")).unwrap();
+    let stash_commit = match stash_push_in_repo(repo_dir.clone(), Some("work")).unwrap() {
+        StashResult::Pushed { commit, .. } => commit,
+        result => panic!("unexpected result {:?}", result),
+    };
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nb\nc\nd\ne\n");
+    match stash_list_in_repo(repo_dir.clone()).unwrap() {
+        StashResult::List { stashes } => {
+            assert_eq!(stashes.len(), 1);
+            assert_eq!(stashes[0].0.commit, stash_commit);
+            assert_eq!(stashes[0].1.message, "On master: work");
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(
+        stash_show_in_repo(repo_dir.clone(), 0).unwrap(),
+        StashResult::Show {
+            file_changes: vec![(FileChange::Modified, PathBuf::from("file"))]
+        }
+    );
+    assert_eq!(
+        stash_show_in_repo(repo_dir.clone(), 1).unwrap(),
+        StashResult::NotFound
+    );
+    match show_in_repo(repo_dir.clone(), "stash@{0}:file").unwrap() {
+        ShowResult::File { contents } => assert_eq!(contents, b"a\nstash\nc\nd\ne\n"),
+        _ => panic!("expected a file"),
+    }
+
+    modify_file(&file, &String::from("a\nb\nc\nhead\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "head").unwrap();
+    assert_eq!(
+        stash_apply_in_repo(repo_dir.clone(), 0, true).unwrap(),
+        StashResult::Applied {
+            commit: stash_commit.clone(),
+            dropped: true
+        }
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nstash\nc\nhead\ne\n");
+    match stash_list_in_repo(repo_dir.clone()).unwrap() {
+        StashResult::List { stashes } => assert!(stashes.is_empty()),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    stash_push_in_repo(repo_dir.clone(), None).unwrap();
+    modify_file(&file, &String::from("a\nconflict\nc\nhead\ne\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "conflict").unwrap();
+    assert_eq!(
+        stash_apply_in_repo(repo_dir.clone(), 0, true).unwrap(),
+        StashResult::MergeConflict {
+            paths: vec![PathBuf::from("file")]
+        }
+    );
+    assert!(matches!(
+        stash_drop_in_repo(repo_dir.clone(), 0).unwrap(),
+        StashResult::Dropped { .. }
+    ));
+    assert_eq!(
+        stash_drop_in_repo(repo_dir.clone(), 0).unwrap(),
+        StashResult::NotFound
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn jump_with_local_changes() {
+    let repo_dir = Path::new(".").join("test_repo18");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let file = repo_dir.join("file");
+    modify_file(&file, &String::from("a\nb\nc\nd\ne\n")).unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "first").unwrap().commit;
+    modify_file(&file, &String::from("a\nb\nc\nD\ne\n")).unwrap();
+    modify_file(&repo_dir.join("new"), &String::from("new")).unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "second").unwrap().commit;
+
+    modify_file(&file, &String::from("a\nlocal\nc\nD\ne\n")).unwrap();
+    assert_eq!(
+        jump_commit_with_mode_in_repo(repo_dir.clone(), "HEAD~1", None, JumpMode::Merge).unwrap(),
+        JumpResult::Success {
+            commit: first_commit.clone(),
+            branch: String::from("master")
+        }
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nlocal\nc\nd\ne\n");
+    assert!(!repo_dir.join("new").exists());
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(FileChange::Modified, file.clone())]
+    );
+
+    assert_eq!(
+        jump_branch_with_mode_in_repo(repo_dir.clone(), "master", JumpMode::Merge).unwrap(),
+        JumpResult::Success {
+            commit: second_commit,
+            branch: String::from("master")
+        }
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nlocal\nc\nD\ne\n");
+    assert!(repo_dir.join("new").exists());
+
+    modify_file(&file, &String::from("a\nlocal\nc\nX\ne\n")).unwrap();
+    assert_eq!(
+        jump_commit_with_mode_in_repo(repo_dir.clone(), &first_commit, None, JumpMode::Merge)
+            .unwrap(),
+        JumpResult::MergeConflict {
+            paths: vec![PathBuf::from("file")]
+        }
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nlocal\nc\nX\ne\n");
+    assert!(matches!(
+        jump_commit_in_repo(repo_dir.clone(), &first_commit, None).unwrap(),
+        JumpResult::UncommitedChanges { .. }
+    ));
+
+    assert_eq!(
+        jump_commit_with_mode_in_repo(repo_dir.clone(), &first_commit, None, JumpMode::Force)
+            .unwrap(),
+        JumpResult::Success {
+            commit: first_commit.clone(),
+            branch: String::from("master")
+        }
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "a\nb\nc\nd\ne\n");
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn log_with_filters() {
+    let repo_dir = Path::new(".").join("test_repo19");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    fs::create_dir(repo_dir.join("docs")).unwrap();
+    modify_file(&repo_dir.join("docs").join("readme"), &String::from("1")).unwrap();
+    let docs_commit = commit_in_repo(repo_dir.clone(), "Add docs").unwrap().commit;
+    modify_file(&repo_dir.join("code"), &String::from("code")).unwrap();
+    let code_commit = commit_in_repo(repo_dir.clone(), "Add code").unwrap().commit;
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(&repo_dir.join("code"), &String::from("2")).unwrap();
+    let feature_commit = commit_in_repo(repo_dir.clone(), "Fix code").unwrap().commit;
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+
+    let hashes = |options: LogOptions| -> Vec<String> {
+        log_with_options_in_repo(repo_dir.clone(), &options)
+            .unwrap()
+            .commit_list
+            .into_iter()
+            .map(|x| x.0.hash)
+            .collect()
+    };
+    assert_eq!(log_in_repo(repo_dir.clone()).unwrap().commit_list.len(), 3);
+    assert_eq!(
+        hashes(LogOptions {
+            max_count: Some(1),
+            ..Default::default()
+        }),
+        vec![code_commit.clone()]
+    );
+    assert_eq!(
+        hashes(LogOptions {
+            grep: Some(String::from("docs")),
+            ..Default::default()
+        }),
+        vec![docs_commit.clone()]
+    );
+    assert_eq!(
+        hashes(LogOptions {
+            paths: vec![PathBuf::from("code")],
+            all: true,
+            ..Default::default()
+        }),
+        vec![feature_commit.clone(), code_commit.clone()]
+    );
+    assert_eq!(
+        hashes(LogOptions {
+            paths: vec![PathBuf::from("docs")],
+            ..Default::default()
+        }),
+        vec![docs_commit.clone()]
+    );
+    assert_eq!(
+        hashes(LogOptions {
+            all: true,
+            ..Default::default()
+        })
+        .len(),
+        4
+    );
+    assert!(hashes(LogOptions {
+        since: Some(parse_date("1 day ago").unwrap() + std::time::Duration::from_secs(2 * 86400)),
+        ..Default::default()
+    })
+    .is_empty());
+    assert!(hashes(LogOptions {
+        author: Some(String::from("nobody with this name")),
+        ..Default::default()
+    })
+    .is_empty());
+    assert_eq!(
+        hashes(LogOptions {
+            until: Some(parse_date("2000-01-01").unwrap()),
+            ..Default::default()
+        }),
+        Vec::<String>::new()
+    );
+    assert!(parse_date("yesterday-ish").is_err());
+
+    let commit_data = &log_in_repo(repo_dir.clone()).unwrap().commit_list[0].0;
+    assert_eq!(
+        format_commit("%h %s%n%H %P %%", commit_data),
+        format!(
+            "{} Add code\n{} {} %",
+            &code_commit[..7],
+            code_commit,
+            docs_commit
+        )
+    );
+
+    let log_result = log_with_options_in_repo(
+        repo_dir.clone(),
+        &LogOptions {
+            skip_changes: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(log_result.commit_list.len(), 3);
+    assert!(log_result.commit_list.iter().all(|x| x.1.is_empty()));
+
+    // A renamed file is found by its old path
+    fs::rename(repo_dir.join("code"), repo_dir.join("moved_code")).unwrap();
+    let rename_commit = commit_in_repo(repo_dir.clone(), "Move code")
+        .unwrap()
+        .commit;
+    assert_eq!(
+        hashes(LogOptions {
+            paths: vec![PathBuf::from("code")],
+            skip_changes: true,
+            ..Default::default()
+        }),
+        vec![rename_commit, code_commit]
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn log_paths_added_in_initial_commit() {
+    let repo_dir = Path::new(".").join("test_repo31");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    modify_file(&repo_dir.join("readme"), &String::from("1")).unwrap();
+    let initial_commit = init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("readme"), &String::from("2")).unwrap();
+    modify_file(&repo_dir.join("code"), &String::from("code")).unwrap();
+    let commit = commit_in_repo(repo_dir.clone(), "Change readme")
+        .unwrap()
+        .commit;
+
+    let log_result = log_with_options_in_repo(
+        repo_dir.clone(),
+        &LogOptions {
+            paths: vec![PathBuf::from("readme")],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let commit_list: Vec<(String, Vec<(FileChange, PathBuf)>)> = log_result
+        .commit_list
+        .into_iter()
+        .map(|x| (x.0.hash, x.1))
+        .collect();
+    let commit_dir = |commit: &str| repo_dir.join(".vcs").join("commits").join(commit);
+    assert_eq!(
+        commit_list,
+        vec![
+            (
+                commit.clone(),
+                vec![
+                    (FileChange::Added, commit_dir(&commit).join("code")),
+                    (FileChange::Modified, commit_dir(&commit).join("readme")),
+                ]
+            ),
+            (
+                initial_commit.clone(),
+                vec![(
+                    FileChange::Added,
+                    commit_dir(&initial_commit).join("readme")
+                )]
+            ),
+        ]
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn log_graph_with_branches() {
+    let repo_dir = Path::new(".").join("test_repo20");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("1")).unwrap();
+    commit_in_repo(repo_dir.clone(), "Fork").unwrap();
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(&repo_dir.join("feature_file"), &String::from("1")).unwrap();
+    let feature_commit = commit_in_repo(repo_dir.clone(), "Feature").unwrap().commit;
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    modify_file(&repo_dir.join("master_file"), &String::from("1")).unwrap();
+    let master_commit = commit_in_repo(repo_dir.clone(), "Master").unwrap().commit;
+
+    let log_result = log_with_options_in_repo(
+        repo_dir.clone(),
+        &LogOptions {
+            all: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        log_result.decorations.get(&master_commit),
+        Some(&vec![String::from("HEAD -> master")])
+    );
+    assert_eq!(
+        log_result.decorations.get(&feature_commit),
+        Some(&vec![String::from("feature")])
+    );
+
+    let commits: Vec<_> = log_result.commit_list.into_iter().map(|x| x.0).collect();
+    let messages: Vec<&str> = commits.iter().map(|x| x.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["Master", "Feature", "Fork", "Initial commit"]
+    );
+    let lines: Vec<String> = draw_graph(&commits)
+        .into_iter()
+        .map(|line| match line {
+            GraphLine::Commit { prefix, index, .. } => format!("{} {}", prefix, messages[index]),
+            GraphLine::Edges(edges) => edges,
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "* Master",
+            "| * Feature",
+            "|/",
+            "* Fork",
+            "* Initial commit"
+        ]
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn blame_lines() {
+    let repo_dir = Path::new(".").join("test_repo21");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    modify_file(&repo_dir.join("file"), &String::from("1\n2\n3\n4\n5\n")).unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "First").unwrap().commit;
+    modify_file(&repo_dir.join("file"), &String::from("one\n2\n3\n4\n5\n")).unwrap();
+    let second_commit = commit_in_repo(repo_dir.clone(), "Second").unwrap().commit;
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    modify_file(
+        &repo_dir.join("file"),
+        &String::from("one\n2\n3\n4\nfive\n"),
+    )
+    .unwrap();
+    let feature_commit = commit_in_repo(repo_dir.clone(), "Feature").unwrap().commit;
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    modify_file(&repo_dir.join("other_file"), &String::from("1")).unwrap();
+    commit_in_repo(repo_dir.clone(), "Other").unwrap();
+    merge_in_repo(repo_dir.clone(), "feature").unwrap();
+
+    let owners = |revision: &str, range: Option<(usize, Option<usize>)>| -> Vec<(usize, String)> {
+        match blame_in_repo(repo_dir.clone(), Path::new("file"), revision, range).unwrap() {
+            BlameResult::Success { lines } => lines
+                .into_iter()
+                .map(|x| (x.line_number, x.commit_data.hash))
+                .collect(),
+            _ => panic!("The file should be blamed"),
+        }
+    };
+    assert_eq!(
+        owners("master", None),
+        vec![
+            (1, second_commit.clone()),
+            (2, first_commit.clone()),
+            (3, first_commit.clone()),
+            (4, first_commit.clone()),
+            (5, feature_commit.clone()),
+        ]
+    );
+    assert_eq!(
+        owners("master~1", Some((1, Some(2)))),
+        vec![(1, second_commit.clone()), (2, first_commit.clone())]
+    );
+    assert_eq!(
+        blame_in_repo(
+            repo_dir.clone(),
+            Path::new("file"),
+            "HEAD",
+            Some((6, Some(7)))
+        )
+        .unwrap(),
+        BlameResult::InvalidRange { lines_count: 5 }
+    );
+    assert!(matches!(
+        blame_in_repo(repo_dir.clone(), Path::new("missing"), "HEAD", None),
+        Err(VcsError::PathNotFound { .. })
+    ));
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn bisect_finds_first_bad_commit() {
+    let repo_dir = Path::new(".").join("test_repo22");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let mut commits: Vec<String> = Vec::new();
+    for i in 0..8 {
+        let flag = if i >= 5 { "bug" } else { "ok" };
+        modify_file(&repo_dir.join("flag"), &String::from(flag)).unwrap();
+        modify_file(&repo_dir.join("number"), &i.to_string()).unwrap();
+        commits.push(
+            commit_in_repo(repo_dir.clone(), &format!("Commit {}", i))
+                .unwrap()
+                .commit,
+        );
+    }
+
+    assert_eq!(
+        bisect_start_in_repo(repo_dir.clone(), None, &[]).unwrap(),
+        BisectResult::Waiting {
+            has_bad: false,
+            has_good: false
+        }
+    );
+    assert_eq!(
+        bisect_start_in_repo(repo_dir.clone(), None, &[]).unwrap(),
+        BisectResult::InProgress
+    );
+    assert_eq!(
+        bisect_mark_in_repo(repo_dir.clone(), BisectMark::Bad, None).unwrap(),
+        BisectResult::Waiting {
+            has_bad: true,
+            has_good: false
+        }
+    );
+    let mut result =
+        bisect_mark_in_repo(repo_dir.clone(), BisectMark::Good, Some(&commits[0])).unwrap();
+    let mut skipped = false;
+    while let BisectResult::Jumped { commit, .. } = result {
+        let mark = if !skipped {
+            skipped = true;
+            BisectMark::Skip
+        } else if fs::read_to_string(repo_dir.join("flag")).unwrap() == "ok" {
+            BisectMark::Good
+        } else {
+            BisectMark::Bad
+        };
+        result = bisect_mark_in_repo(repo_dir.clone(), mark, Some(&commit)).unwrap();
+    }
+    match result {
+        BisectResult::Found { commit_data } => assert_eq!(commit_data.hash, commits[5]),
+        result => panic!("Unexpected bisect result {:?}", result),
+    }
+    assert_eq!(
+        bisect_reset_in_repo(repo_dir.clone()).unwrap(),
+        BisectResult::Reset {
+            commit: commits[7].clone(),
+            branch: String::from("master")
+        }
+    );
+    assert!(!repo_dir.join(".vcs").join("bisect.json").exists());
+
+    bisect_start_in_repo(repo_dir.clone(), Some("HEAD"), &[commits[0].clone()]).unwrap();
+    assert!(matches!(
+        bisect_run_in_repo(repo_dir.clone(), &[]),
+        Err(VcsError::EmptyCommand)
+    ));
+    let command = ["sh", "-c", "grep -q ok flag"].map(String::from);
+    match bisect_run_in_repo(repo_dir.clone(), &command).unwrap() {
+        BisectResult::Found { commit_data } => assert_eq!(commit_data.hash, commits[5]),
+        result => panic!("Unexpected bisect result {:?}", result),
+    }
+    bisect_reset_in_repo(repo_dir.clone()).unwrap();
+    assert_eq!(
+        bisect_reset_in_repo(repo_dir.clone()).unwrap(),
+        BisectResult::NotInProgress
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn grep_working_tree_and_commits() {
+    let repo_dir = Path::new(".").join("test_repo23");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    fs::create_dir(repo_dir.join("src")).unwrap();
+    modify_file(
+        &repo_dir.join("src").join("main"),
+        &String::from("let x = 1;\nTODO fix\n"),
+    )
+    .unwrap();
+    modify_file(&repo_dir.join("notes"), &String::from("todo: release\n")).unwrap();
+    let old_commit = commit_in_repo(repo_dir.clone(), "Old").unwrap().commit;
+    modify_file(
+        &repo_dir.join("src").join("main"),
+        &String::from("let x = 2;\n"),
+    )
+    .unwrap();
+    commit_in_repo(repo_dir.clone(), "New").unwrap();
+
+    let found = |pattern: &str, revisions: &[String], options: &GrepOptions| -> Vec<String> {
+        grep_in_repo(repo_dir.clone(), pattern, revisions, options)
+            .unwrap()
+            .matches
+            .into_iter()
+            .map(|grep_match| match grep_match {
+                GrepMatch::Line {
+                    revision,
+                    path,
+                    line_number,
+                    line,
+                } => format!(
+                    "{}{}:{}:{}",
+                    revision.map(|x| x + ":").unwrap_or_default(),
+                    path.display(),
+                    line_number,
+                    String::from_utf8(line).unwrap()
+                ),
+                GrepMatch::Binary { path, .. } => path.display().to_string(),
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        found("TODO", &[], &GrepOptions::default()),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        found(
+            "todo",
+            &[],
+            &GrepOptions {
+                ignore_case: true,
+                ..Default::default()
+            }
+        ),
+        vec!["notes:1:todo: release"]
+    );
+    assert_eq!(
+        found(
+            r"x = \d",
+            &[String::from("HEAD"), old_commit.clone()],
+            &GrepOptions::default()
+        ),
+        vec![
+            String::from("HEAD:src/main:1:let x = 2;"),
+            format!("{}:src/main:1:let x = 1;", old_commit)
+        ]
+    );
+    assert_eq!(
+        found(
+            "e",
+            &[String::from("HEAD~1")],
+            &GrepOptions {
+                paths: vec![PathBuf::from("src")],
+                ..Default::default()
+            }
+        ),
+        vec!["HEAD~1:src/main:1:let x = 1;"]
+    );
+    assert!(matches!(
+        grep_in_repo(repo_dir.clone(), "(", &[], &GrepOptions::default()),
+        Err(VcsError::InvalidPattern(_))
+    ));
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn renames_in_status_and_merge() {
+    let repo_dir = Path::new(".").join("test_repo24");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let lines = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    modify_file(&repo_dir.join("doc"), &String::from(lines)).unwrap();
+    modify_file(&repo_dir.join("code"), &String::from(lines)).unwrap();
+    modify_file(&repo_dir.join("hello"), &String::from("hello\n")).unwrap();
+    commit_in_repo(repo_dir.clone(), "Files").unwrap();
+
+    fs::rename(repo_dir.join("hello"), repo_dir.join("greeting")).unwrap();
+    modify_file(&repo_dir.join("greeting"), &String::from("hello\nworld\n")).unwrap();
+    fs::copy(repo_dir.join("doc"), repo_dir.join("doc_copy")).unwrap();
+    let mut file_changes = status_in_repo(repo_dir.clone()).unwrap().file_changes;
+    file_changes.sort_by(|x, y| x.1.cmp(&y.1));
+    assert_eq!(
+        file_changes,
+        vec![
+            (
+                FileChange::Copied {
+                    from: repo_dir.join("doc"),
+                    similarity: 100
+                },
+                repo_dir.join("doc_copy")
+            ),
+            (
+                FileChange::Renamed {
+                    from: repo_dir.join("hello"),
+                    similarity: 66
+                },
+                repo_dir.join("greeting")
+            ),
+        ]
+    );
+    commit_in_repo(repo_dir.clone(), "Rename and copy").unwrap();
+
+    new_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "feature").unwrap();
+    fs::rename(repo_dir.join("doc"), repo_dir.join("guide")).unwrap();
+    modify_file(
+        &repo_dir.join("code"),
+        &String::from("1\n2\n3\n4\n5\n6\n7\neight\n"),
+    )
+    .unwrap();
+    commit_in_repo(repo_dir.clone(), "Feature").unwrap();
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    modify_file(
+        &repo_dir.join("doc"),
+        &String::from("one\n2\n3\n4\n5\n6\n7\n8\n"),
+    )
+    .unwrap();
+    fs::rename(repo_dir.join("code"), repo_dir.join("main_code")).unwrap();
+    commit_in_repo(repo_dir.clone(), "Master").unwrap();
+
+    let file = |path: &str| match show_in_repo(repo_dir.clone(), &format!("master:{}", path)) {
+        Ok(ShowResult::File { contents }) => Some(String::from_utf8(contents).unwrap()),
+        _ => None,
+    };
+    assert!(matches!(
+        merge_in_repo(repo_dir.clone(), "feature").unwrap(),
+        MergeResult::Success { .. }
+    ));
+    assert_eq!(
+        file("guide"),
+        Some(String::from("one\n2\n3\n4\n5\n6\n7\n8\n"))
+    );
+    assert_eq!(
+        file("main_code"),
+        Some(String::from("1\n2\n3\n4\n5\n6\n7\neight\n"))
+    );
+    assert_eq!(file("doc"), None);
+    assert_eq!(file("code"), None);
+    assert_eq!(file("doc_copy"), Some(String::from(lines)));
+
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    let long: String = (0..=SIMILARITY_MAX_LINES)
+        .map(|x| format!("{}\n", x))
+        .collect();
+    modify_file(&repo_dir.join("long"), &long).unwrap();
+    assert!(
+        commit_in_repo(repo_dir.clone(), "Long file")
+            .unwrap()
+            .successful
+    );
+    fs::rename(repo_dir.join("long"), repo_dir.join("moved_long")).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(
+            FileChange::Renamed {
+                from: repo_dir.join("long"),
+                similarity: 100
+            },
+            repo_dir.join("moved_long")
+        )]
+    );
+    modify_file(&repo_dir.join("moved_long"), &format!("{}end\n", long)).unwrap();
+    let mut file_changes = status_in_repo(repo_dir.clone()).unwrap().file_changes;
+    file_changes.sort_by(|x, y| x.1.cmp(&y.1));
+    assert_eq!(
+        file_changes,
+        vec![
+            (FileChange::Removed, repo_dir.join("long")),
+            (FileChange::Added, repo_dir.join("moved_long")),
+        ]
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn modes_symlinks_and_empty_folders() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let repo_dir = Path::new(".").join("test_repo25");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let script = repo_dir.join("script");
+    modify_file(&script, &String::from("echo hi\n")).unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+    symlink("script", repo_dir.join("link")).unwrap();
+    symlink("missing", repo_dir.join("dangling")).unwrap();
+    fs::create_dir(repo_dir.join("empty")).unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "Files").unwrap().commit;
+
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(FileChange::ModeChanged { executable: true }, script.clone())]
+    );
+    commit_in_repo(repo_dir.clone(), "Executable").unwrap();
+
+    let mode = |path: &PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    jump_commit_in_repo(repo_dir.clone(), &first_commit, None).unwrap();
+    assert_eq!(mode(&script), 0o644);
+    assert_eq!(
+        fs::read_link(repo_dir.join("link")).unwrap(),
+        PathBuf::from("script")
+    );
+    assert_eq!(
+        fs::read_link(repo_dir.join("dangling")).unwrap(),
+        PathBuf::from("missing")
+    );
+    assert!(repo_dir.join("empty").is_dir());
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    assert_eq!(mode(&script), 0o755);
+    match show_in_repo(repo_dir.clone(), "master:link").unwrap() {
+        ShowResult::File { contents } => assert_eq!(contents, b"script"),
+        _ => panic!("Expected the symlink target"),
+    }
+
+    fs::remove_file(repo_dir.join("link")).unwrap();
+    symlink("other", repo_dir.join("link")).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(FileChange::Modified, repo_dir.join("link"))]
+    );
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let repo_dir = Path::new(".").join("test_repo26");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let first_commit = init(repo_dir.clone()).unwrap();
+    let name = OsStr::from_bytes(b"caf\xe9\n\"x\"");
+    let path = repo_dir.join(name);
+    modify_file(&path, &String::from("content")).unwrap();
+    assert_eq!(
+        escape_path(Path::new(name)),
+        String::from("\"caf\\351\\n\\\"x\\\"\"")
+    );
+    assert_eq!(escape_path(Path::new("dir/café")), String::from("dir/café"));
+
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(FileChange::Added, path.clone())]
+    );
+    commit_in_repo(repo_dir.clone(), "Latin-1 name").unwrap();
+    jump_commit_in_repo(repo_dir.clone(), &first_commit, None).unwrap();
+    assert!(!path.exists());
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"content");
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn large_files() {
+    let repo_dir = Path::new(".").join("test_repo27");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    let mut data: Vec<u8> = (0..3 << 20).map(|x: u32| (x % 251) as u8).collect();
+    fs::write(repo_dir.join("asset"), &data).unwrap();
+    commit_in_repo(repo_dir.clone(), "Asset").unwrap();
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    *data.last_mut().unwrap() ^= 1;
+    fs::write(repo_dir.join("asset"), &data).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(FileChange::Modified, repo_dir.join("asset"))]
+    );
+    commit_in_repo(repo_dir.clone(), "Changed asset").unwrap();
+
+    fs::rename(repo_dir.join("asset"), repo_dir.join("moved")).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(
+            FileChange::Renamed {
+                from: repo_dir.join("asset"),
+                similarity: 100
+            },
+            repo_dir.join("moved")
+        )]
+    );
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn large_objects_fetch_and_prune() {
+    let repo_dir = Path::new(".").join("test_repo28");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    init(repo_dir.clone()).unwrap();
+    lfs_track_in_repo(repo_dir.clone(), "*.bin").unwrap();
+    assert_eq!(
+        lfs_threshold_in_repo(repo_dir.clone(), Some(1000)).unwrap(),
+        LfsResult::Config {
+            config: LargeFilesConfig {
+                threshold: Some(1000),
+                patterns: vec![String::from("*.bin")]
+            }
+        }
+    );
+    modify_file(&repo_dir.join("asset.bin"), &String::from("asset v1")).unwrap();
+    fs::write(repo_dir.join("big.dat"), vec![0u8; 4000]).unwrap();
+    fs::write(repo_dir.join("text.dat"), vec![b'a'; 4000]).unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "Assets").unwrap().commit;
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+
+    let pointer = match show_in_repo(repo_dir.clone(), "HEAD:asset.bin").unwrap() {
+        ShowResult::File { contents } => Pointer::parse(&contents).unwrap(),
+        _ => panic!("Expected a pointer file"),
+    };
+    assert_eq!(pointer.size, 8);
+    match lfs_list_in_repo(repo_dir.clone(), "HEAD").unwrap() {
+        LfsResult::List { files } => {
+            let paths: Vec<&Path> = files.iter().map(|x| x.path.as_path()).collect();
+            assert_eq!(paths, vec![Path::new("asset.bin"), Path::new("big.dat")]);
+            assert!(files.iter().all(|x| x.present));
+        }
+        _ => panic!("Expected a list of large files"),
+    }
+
+    modify_file(&repo_dir.join("asset.bin"), &String::from("asset v2")).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![(FileChange::Modified, repo_dir.join("asset.bin"))]
+    );
+    commit_in_repo(repo_dir.clone(), "New asset").unwrap();
+    jump_commit_in_repo(repo_dir.clone(), &first_commit, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("asset.bin")).unwrap(),
+        "asset v1"
+    );
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+
+    assert_eq!(
+        lfs_prune_in_repo(repo_dir.clone()).unwrap(),
+        LfsResult::Pruned {
+            objects: 1,
+            bytes: 8
+        }
+    );
+    jump_commit_in_repo(repo_dir.clone(), &first_commit, None).unwrap();
+    assert_eq!(
+        fs::read(repo_dir.join("asset.bin")).unwrap(),
+        pointer.to_bytes()
+    );
+    assert_eq!(fs::read(repo_dir.join("big.dat")).unwrap(), vec![0u8; 4000]);
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("asset.bin")).unwrap(),
+        "asset v2"
+    );
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+    assert_eq!(
+        lfs_untrack_in_repo(repo_dir.clone(), "*.png").unwrap(),
+        LfsResult::NotTracked
+    );
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
+
+#[test]
+fn sha256_repository() {
+    let repo_dir = Path::new(".").join("test_repo29");
+    if Path::exists(&repo_dir) {
+        fs::remove_dir_all(&repo_dir).unwrap();
+    }
+    fs::create_dir(&repo_dir).unwrap();
+
+    let initial_commit = init_with_hash(repo_dir.clone(), HashAlgorithm::Sha256).unwrap();
+    assert_eq!(initial_commit.len(), 64);
+    assert_eq!(get_format(&repo_dir).unwrap().hash, HashAlgorithm::Sha256);
+
+    lfs_track_in_repo(repo_dir.clone(), "*.bin").unwrap();
+    modify_file(&repo_dir.join("file.txt"), &String::from("first")).unwrap();
+    modify_file(&repo_dir.join("asset.bin"), &String::from("asset")).unwrap();
+    let first_commit = commit_in_repo(repo_dir.clone(), "First").unwrap().commit;
+    assert_eq!(first_commit.len(), 64);
+    assert!(status_in_repo(repo_dir.clone())
+        .unwrap()
+        .file_changes
+        .is_empty());
+    match lfs_list_in_repo(repo_dir.clone(), "HEAD").unwrap() {
+        LfsResult::List { files } => {
+            assert_eq!(files.len(), 1);
+            assert_eq!(files[0].pointer.hash.len(), 64);
+        }
+        _ => panic!("Expected a list of large files"),
+    }
+
+    // Renames are detected with the hashes of the repository
+    fs::rename(repo_dir.join("asset.bin"), repo_dir.join("moved.bin")).unwrap();
+    modify_file(&repo_dir.join("file.txt"), &String::from("second")).unwrap();
+    assert_eq!(
+        status_in_repo(repo_dir.clone()).unwrap().file_changes,
+        vec![
+            (FileChange::Modified, repo_dir.join("file.txt")),
+            (
+                FileChange::Renamed {
+                    from: repo_dir.join("asset.bin"),
+                    similarity: 100
+                },
+                repo_dir.join("moved.bin")
+            ),
+        ]
+    );
+    commit_in_repo(repo_dir.clone(), "Second").unwrap();
+
+    jump_commit_in_repo(repo_dir.clone(), &first_commit[..10], None).unwrap();
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("file.txt")).unwrap(),
+        "first"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("asset.bin")).unwrap(),
+        "asset"
+    );
+    jump_branch_in_repo(repo_dir.clone(), "master").unwrap();
+    assert!(fsck_in_repo(repo_dir.clone(), false)
+        .unwrap()
+        .problems
+        .is_empty());
+
+    fs::remove_dir_all(&repo_dir).unwrap();
+}
@@ -1,5 +1,8 @@
+#![allow(clippy::needless_range_loop, clippy::ptr_arg, clippy::useless_conversion)]
+
 use std::{path::{Path, PathBuf}, fs::{self, File}};
 
+use vcs::diff::{diff_lines, merge_lines, split_lines, DiffLine, MergedText};
 use vcs::commands::{init::init, commit::commit_in_repo, jump::{jump_commit_in_repo, jump_branch_in_repo, JumpResult}, new_branch::new_branch_in_repo, merge::merge_in_repo};
 
 #[test]
@@ -36,7 +39,7 @@ fn create_branches(repo_dir: &PathBuf, branches_count: usize) -> Vec<String> {
 
     let mut commits: Vec<String> = Vec::new();
     for branch_num in 0..branches_count {
-        jump_commit_in_repo(repo_dir.clone(), &first_commit, Some(&String::from("master"))).unwrap();
+        jump_commit_in_repo(repo_dir.clone(), &first_commit, Some("master")).unwrap();
         new_branch_in_repo(repo_dir.clone(), &String::from(branch_num.to_string())).unwrap();
         jump_branch_in_repo(repo_dir.clone(), &String::from(branch_num.to_string())).unwrap();
 
@@ -103,3 +106,160 @@ fn test_merge() {
 
     fs::remove_dir_all(&repo_dir).unwrap();
 }
+
+/// Check that the edit script turns the old lines into the new ones
+fn assert_valid_diff(old: &[&[u8]], new: &[&[u8]], lines: &[DiffLine]) {
+    let mut old_lines: Vec<&[u8]> = Vec::new();
+    let mut new_lines: Vec<&[u8]> = Vec::new();
+    for line in lines {
+        match *line {
+            DiffLine::Equal { old: x, new: y } => {
+                assert_eq!(old[x], new[y]);
+                old_lines.push(old[x]);
+                new_lines.push(new[y]);
+            }
+            DiffLine::Removed { old: x } => old_lines.push(old[x]),
+            DiffLine::Added { new: y } => new_lines.push(new[y]),
+        }
+    }
+    assert_eq!(old_lines, old);
+    assert_eq!(new_lines, new);
+}
+
+fn count_changes(lines: &[DiffLine]) -> usize {
+    lines.iter().filter(|x| !matches!(x, DiffLine::Equal { .. })).count()
+}
+
+fn merge(base: &str, ours: &str, theirs: &str) -> MergedText {
+    merge_lines(
+        &split_lines(base.as_bytes()),
+        &split_lines(ours.as_bytes()),
+        &split_lines(theirs.as_bytes()),
+        "ours",
+        "theirs",
+    )
+}
+
+#[test]
+fn test_diff_empty_inputs() {
+    let empty: Vec<&[u8]> = Vec::new();
+    let lines = split_lines(b"a\nb\n");
+    assert!(diff_lines(&empty, &empty).is_empty());
+    assert_eq!(
+        diff_lines(&empty, &lines),
+        vec![DiffLine::Added { new: 0 }, DiffLine::Added { new: 1 }]
+    );
+    assert_eq!(
+        diff_lines(&lines, &empty),
+        vec![DiffLine::Removed { old: 0 }, DiffLine::Removed { old: 1 }]
+    );
+}
+
+#[test]
+fn test_diff_identical_and_disjoint() {
+    let old = split_lines(b"a\nb\nc\n");
+    let lines = diff_lines(&old, &old);
+    assert_eq!(count_changes(&lines), 0);
+    assert_valid_diff(&old, &old, &lines);
+
+    let new = split_lines(b"x\ny\n");
+    let lines = diff_lines(&old, &new);
+    assert_eq!(count_changes(&lines), old.len() + new.len());
+    assert_valid_diff(&old, &new, &lines);
+}
+
+#[test]
+fn test_diff_is_shortest() {
+    // The example from Myers' paper has an edit distance of 5
+    let old: Vec<&[u8]> = b"abcabba".chunks(1).collect();
+    let new: Vec<&[u8]> = b"cbabac".chunks(1).collect();
+    let lines = diff_lines(&old, &new);
+    assert_eq!(count_changes(&lines), 5);
+    assert_valid_diff(&old, &new, &lines);
+}
+
+#[test]
+fn test_diff_large_rewrite() {
+    // Every other line changes, the edit distance is as large as the files
+    let old_text: String = (0..5000).map(|x| format!("{}\n", x)).collect();
+    let new_text: String = (0..5000)
+        .map(|x| if x % 2 == 0 { format!("{}\n", x) } else { format!("new {}\n", x) })
+        .collect();
+    let old = split_lines(old_text.as_bytes());
+    let new = split_lines(new_text.as_bytes());
+    let lines = diff_lines(&old, &new);
+    assert_eq!(count_changes(&lines), 5000);
+    assert_valid_diff(&old, &new, &lines);
+}
+
+#[test]
+fn test_diff_missing_trailing_newline() {
+    let old = split_lines(b"a\nb");
+    let new = split_lines(b"a\nb\n");
+    assert_eq!(
+        diff_lines(&old, &new),
+        vec![
+            DiffLine::Equal { old: 0, new: 0 },
+            DiffLine::Removed { old: 1 },
+            DiffLine::Added { new: 1 },
+        ]
+    );
+}
+
+#[test]
+fn test_merge_without_conflicts() {
+    let base = "a\nb\nc\nd\ne\n";
+    let merged = merge(base, "a\nB\nc\nd\ne\n", "a\nb\nc\nD\ne\n");
+    assert_eq!(merged.data, b"a\nB\nc\nD\ne\n");
+    assert_eq!(merged.conflicts, 0);
+
+    // The same change on both sides is taken once
+    let merged = merge(base, "a\nb\nX\nd\ne\n", "a\nb\nX\nd\ne\n");
+    assert_eq!(merged.data, b"a\nb\nX\nd\ne\n");
+    assert_eq!(merged.conflicts, 0);
+
+    let merged = merge(base, base, base);
+    assert_eq!(merged.data, base.as_bytes());
+    assert_eq!(merged.conflicts, 0);
+
+    let merged = merge("", "a\n", "");
+    assert_eq!(merged.data, b"a\n");
+    assert_eq!(merged.conflicts, 0);
+}
+
+#[test]
+fn test_merge_conflicts() {
+    let base = "a\nb\nc\nd\ne\n";
+    let merged = merge(base, "a\nb\nX\nd\ne\n", "a\nb\nY\nd\ne\n");
+    assert_eq!(
+        merged.data,
+        b"a\nb\n<<<<<<< ours\nX\n=======\nY\n>>>>>>> theirs\nd\ne\n"
+    );
+    assert_eq!(merged.conflicts, 1);
+
+    // Overlapping changes conflict as a whole
+    let merged = merge(base, "a\nB\nC\nd\ne\n", "a\nb\nC2\nD\ne\n");
+    assert_eq!(
+        merged.data,
+        b"a\n<<<<<<< ours\nB\nC\nd\n=======\nb\nC2\nD\n>>>>>>> theirs\ne\n"
+    );
+    assert_eq!(merged.conflicts, 1);
+
+    let merged = merge("", "a\n", "b\n");
+    assert_eq!(merged.data, b"<<<<<<< ours\na\n=======\nb\n>>>>>>> theirs\n");
+    assert_eq!(merged.conflicts, 1);
+}
+
+#[test]
+fn test_merge_missing_trailing_newline() {
+    let merged = merge("a\nb", "a\nx", "a\ny");
+    assert_eq!(
+        merged.data,
+        b"a\n<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\n"
+    );
+    assert_eq!(merged.conflicts, 1);
+
+    let merged = merge("a\nm\nb\n", "a\nm\nb", "A\nm\nb\n");
+    assert_eq!(merged.data, b"A\nm\nb");
+    assert_eq!(merged.conflicts, 0);
+}